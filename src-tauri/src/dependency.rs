@@ -0,0 +1,816 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::Window;
+
+use crate::detector::DependencyStatus;
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::{CancellationToken, CommandExecutor};
+use crate::git_source;
+use crate::installer::{emit_progress, run_checked, run_logged, DOWNLOAD_TIMEOUT, SHORT_TIMEOUT};
+use crate::journal::{InstallJournal, UndoAction};
+use crate::node_env::{home_dir, NodeEnvironment};
+use crate::npm_registry;
+use crate::openclaw_cli::OpenClawCli;
+use crate::platform;
+use crate::version::{self, CalVer, SemVer};
+
+// 单条检测命令的超时时间，防止某个工具卡住导致整个检测页面无响应
+const DETECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 依赖的声明：版本约束、是否必需、前置依赖和安装方式
+pub struct Requirements {
+    pub display_name: &'static str,
+    /// 缺失时是否阻止安装 OpenClaw
+    pub required: bool,
+    /// 版本要求，如 "22+"、"latest"、"any"（可被 `OPENCLAW_INSTALLER_MIN_<NAME>` 覆盖）
+    pub version: String,
+    /// 安装前必须就绪的依赖
+    pub depends_on: Vec<&'static str>,
+    /// 展示给用户的安装命令；为空表示不能单独安装
+    pub install_command: Option<String>,
+}
+
+/// 检测结果
+pub struct Detection {
+    pub installed: bool,
+    pub current_version: Option<String>,
+}
+
+impl Detection {
+    fn missing() -> Self {
+        Self {
+            installed: false,
+            current_version: None,
+        }
+    }
+
+    fn found(version: impl Into<String>) -> Self {
+        Self {
+            installed: true,
+            current_version: Some(version.into()),
+        }
+    }
+}
+
+/// 一项系统依赖：如何检测、如何安装、依赖谁
+///
+/// 新增依赖只需实现该 trait 并加入 [`registry`]。
+#[async_trait]
+pub trait Dependency: Send + Sync {
+    /// 唯一名称，前端和 `install_dependency` 使用
+    fn name(&self) -> &'static str;
+
+    /// 是否适用于当前平台
+    fn applies(&self) -> bool {
+        true
+    }
+
+    fn requirements(&self, node_env: &NodeEnvironment) -> Requirements;
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection;
+
+    async fn install(
+        &self,
+        _window: &Window,
+        _journal: &mut InstallJournal,
+        _cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        Err(InstallerError::Unsupported {
+            message: format!("{} 不能单独安装", self.name()),
+        })
+    }
+
+    /// 合并声明与检测结果
+    async fn status(&self, node_env: &NodeEnvironment) -> DependencyStatus {
+        let requirements = self.requirements(node_env);
+        let detection = self.detect(node_env).await;
+        to_status(self.name(), requirements, detection)
+    }
+
+    /// 安装后仍未就绪时给用户的排查提示
+    fn verify_hint(&self) -> &'static str {
+        ""
+    }
+
+    /// 安装后重新定位工具链并检测，未安装或版本不满足要求时返回带诊断信息的错误
+    async fn verify(&self) -> InstallerResult<DependencyStatus> {
+        let status = self.status(&NodeEnvironment::resolve()).await;
+        if status.is_ready() {
+            return Ok(status);
+        }
+        Err(InstallerError::VerificationFailed {
+            name: status.display_name,
+            required_version: status.required_version,
+            current_version: status.current_version,
+            hint: self.verify_hint().to_string(),
+        })
+    }
+}
+
+/// 所有适用于当前平台的依赖，按检测页面的展示顺序排列
+pub fn registry() -> Vec<Box<dyn Dependency>> {
+    let all: Vec<Box<dyn Dependency>> = vec![
+        Box::new(OpenClaw),
+        Box::new(Nvm),
+        Box::new(NodeJs),
+        Box::new(Npm),
+        Box::new(Corepack),
+        Box::new(Pnpm),
+        Box::new(Git),
+        Box::new(XcodeTools),
+    ];
+    all.into_iter().filter(|dep| dep.applies()).collect()
+}
+
+/// 给定依赖及其全部前置依赖，按安装顺序排列（前置依赖在前）
+pub fn install_order(
+    names: &[&str],
+    node_env: &NodeEnvironment,
+) -> InstallerResult<Vec<Box<dyn Dependency>>> {
+    order_from(registry(), names, node_env)
+}
+
+fn order_from(
+    deps: Vec<Box<dyn Dependency>>,
+    names: &[&str],
+    node_env: &NodeEnvironment,
+) -> InstallerResult<Vec<Box<dyn Dependency>>> {
+    let mut all: HashMap<&'static str, Box<dyn Dependency>> =
+        deps.into_iter().map(|dep| (dep.name(), dep)).collect();
+
+    // OpenClaw 是最终的安装目标，没有依赖以它为前置，总是排在最后（nvm → Node.js → pnpm → OpenClaw）
+    let mut names = names.to_vec();
+    names.sort_by_key(|name| *name == npm_registry::PACKAGE_NAME);
+
+    let mut order = Vec::new();
+    let mut visiting = Vec::new();
+    for name in &names {
+        let name = all
+            .get(name)
+            .map(|dep| dep.name())
+            .ok_or_else(|| InstallerError::UnknownDependency {
+                name: name.to_string(),
+            })?;
+        visit(name, &all, node_env, &mut visiting, &mut order)?;
+    }
+
+    Ok(order.into_iter().filter_map(|name| all.remove(name)).collect())
+}
+
+// 深度优先的拓扑排序；前置依赖不适用于当前平台时忽略
+fn visit(
+    name: &'static str,
+    all: &HashMap<&'static str, Box<dyn Dependency>>,
+    node_env: &NodeEnvironment,
+    visiting: &mut Vec<&'static str>,
+    order: &mut Vec<&'static str>,
+) -> InstallerResult<()> {
+    if order.contains(&name) {
+        return Ok(());
+    }
+    if visiting.contains(&name) {
+        visiting.push(name);
+        return Err(InstallerError::InvalidOption {
+            message: format!("依赖关系存在循环: {}", visiting.join(" → ")),
+        });
+    }
+    let Some(dep) = all.get(name) else {
+        return Ok(());
+    };
+
+    visiting.push(name);
+    for prerequisite in dep.requirements(node_env).depends_on {
+        if all.contains_key(prerequisite) {
+            visit(prerequisite, all, node_env, visiting, order)?;
+        }
+    }
+    visiting.pop();
+    order.push(name);
+    Ok(())
+}
+
+// 辅助函数
+
+fn to_status(name: &str, requirements: Requirements, detection: Detection) -> DependencyStatus {
+    let needs_update = version::needs_update(detection.current_version.as_deref(), &requirements.version);
+    DependencyStatus {
+        name: name.to_string(),
+        display_name: requirements.display_name.to_string(),
+        required: requirements.required,
+        required_version: requirements.version,
+        current_version: detection.current_version,
+        installed: detection.installed,
+        needs_update,
+        install_command: requirements.install_command,
+        depends_on: requirements.depends_on.iter().map(|d| d.to_string()).collect(),
+    }
+}
+
+// 运行 `<program> --version` 一类的检测命令，成功时返回 stdout
+async fn probe(command: CommandExecutor) -> Option<String> {
+    command
+        .timeout(DETECT_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)
+        .map(|output| output.stdout)
+}
+
+// 解析 SemVer，解析不出时保留原始输出
+fn semver_detection(stdout: Option<String>) -> Detection {
+    match stdout {
+        Some(stdout) => Detection::found(
+            SemVer::parse(&stdout)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| stdout.trim().to_string()),
+        ),
+        None => Detection::missing(),
+    }
+}
+
+// 各依赖的声明
+
+struct OpenClaw;
+
+#[async_trait]
+impl Dependency for OpenClaw {
+    fn name(&self) -> &'static str {
+        "openclaw"
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "OpenClaw",
+            required: false, // 检测阶段不标记为必需，让用户选择
+            version: version::configured_minimum("openclaw", "latest"),
+            depends_on: vec!["nodejs", "npm"],
+            install_command: None, // OpenClaw 安装由主流程处理
+        }
+    }
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection {
+        let output = match OpenClawCli::from_env(node_env) {
+            Ok(cli) => probe(cli.command(["--version"])).await,
+            Err(_) => None,
+        };
+
+        match output {
+            // 提取版本号，格式如 "🦞 OpenClaw 2026.2.14 (c1feda1) ..."
+            Some(stdout) => Detection::found(
+                CalVer::parse(&stdout)
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| stdout.lines().next().unwrap_or("unknown").trim().to_string()),
+            ),
+            None => Detection::missing(),
+        }
+    }
+
+    async fn status(&self, node_env: &NodeEnvironment) -> DependencyStatus {
+        let mut requirements = self.requirements(node_env);
+        // 未指定最低版本时，以 npm registry 上的 latest 为准；查询失败则保持 "latest"。
+        // 查询与本地检测同时进行，离线时不会叠加等待
+        let lookup = async {
+            match requirements.version.as_str() {
+                "latest" => npm_registry::cached_latest_version(node_env).await,
+                _ => None,
+            }
+        };
+        let (latest, detection) = tokio::join!(lookup, self.detect(node_env));
+        if let Some(latest) = latest {
+            requirements.version = latest;
+        }
+        to_status(self.name(), requirements, detection)
+    }
+}
+
+struct Nvm;
+
+#[async_trait]
+impl Dependency for Nvm {
+    fn name(&self) -> &'static str {
+        "nvm"
+    }
+
+    fn requirements(&self, node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "nvm (Node Version Manager)",
+            // 已经通过 fnm、volta 等其他工具提供 Node.js 时，nvm 不再是必需的
+            required: matches!(
+                node_env.manager,
+                crate::node_env::NodeManager::Nvm | crate::node_env::NodeManager::None
+            ),
+            version: version::configured_minimum("nvm", "0.39+"),
+            depends_on: vec![],
+            install_command: Some(
+                "curl -o- https://raw.githubusercontent.com/nvm-sh/nvm/v0.39.7/install.sh | bash".to_string(),
+            ),
+        }
+    }
+
+    fn verify_hint(&self) -> &'static str {
+        "未在 NVM_DIR 或 ~/.nvm 下找到 nvm.sh，请检查 install.sh 的输出"
+    }
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection {
+        // 检测 nvm 是否存在（NVM_DIR 或 ~/.nvm 下有 nvm.sh）
+        let Some(dir) = &node_env.nvm_dir else {
+            return Detection::missing();
+        };
+        // nvm 是 git 仓库，版本号记录在 package.json 中
+        let version = std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+            .and_then(|json| json["version"].as_str().map(str::to_string))
+            .unwrap_or_else(|| "installed".to_string());
+        Detection::found(version)
+    }
+
+    async fn install(
+        &self,
+        window: &Window,
+        journal: &mut InstallJournal,
+        cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        emit_progress(window, "安装 nvm (Node Version Manager)", "running", 0.0, vec![]);
+
+        // install.sh 会创建 ~/.nvm，并在 shell 配置文件末尾追加加载 nvm 的代码
+        let home = home_dir();
+        journal.track_new_path(&home.join(".nvm"));
+        for profile in [".bashrc", ".bash_profile", ".zshrc", ".profile"] {
+            let profile = home.join(profile);
+            if profile.exists() {
+                journal.snapshot(&profile)?;
+            }
+        }
+
+        run_checked(
+            window,
+            "nvm",
+            "nvm install.sh",
+            CommandExecutor::shell("curl -o- https://raw.githubusercontent.com/nvm-sh/nvm/v0.39.7/install.sh | bash")
+                .timeout(DOWNLOAD_TIMEOUT)
+                .cancel_token(cancel),
+        )
+        .await?;
+
+        emit_progress(window, "nvm 安装脚本执行完成，终端中需重启或运行 source ~/.nvm/nvm.sh", "running", 0.0, vec![]);
+        Ok(())
+    }
+}
+
+struct NodeJs;
+
+#[async_trait]
+impl Dependency for NodeJs {
+    fn name(&self) -> &'static str {
+        "nodejs"
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "Node.js",
+            required: true,
+            version: version::configured_minimum("nodejs", "22+"),
+            // 安装方式是 nvm install
+            depends_on: vec!["nvm"],
+            install_command: Some("nvm install 22 && nvm use 22 && nvm alias default 22".to_string()),
+        }
+    }
+
+    fn verify_hint(&self) -> &'static str {
+        "nvm 安装的 Node.js 没有成为默认版本，请运行 nvm alias default 22 后重新检测"
+    }
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection {
+        match probe(node_env.command("node").arg("--version")).await {
+            Some(stdout) => Detection::found(stdout.trim()),
+            None => Detection::missing(),
+        }
+    }
+
+    async fn install(
+        &self,
+        window: &Window,
+        journal: &mut InstallJournal,
+        cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        // 先确认官方预编译包能在当前系统上运行，避免 nvm 下载后才失败
+        let warning = platform::check_node_binary_support().await?;
+
+        emit_progress(window, "通过 nvm 安装 Node.js 22", "running", 0.0, warning.into_iter().collect());
+
+        // 中途失败的 nvm install 会留下不完整的版本目录，默认别名也可能已被改写
+        let node_env = NodeEnvironment::resolve();
+        let nvm_dir = node_env.nvm_dir.clone().unwrap_or_else(|| home_dir().join(".nvm"));
+        journal.track_new_entries(&nvm_dir.join("versions").join("node"));
+        journal.snapshot(&nvm_dir.join("alias").join("default"))?;
+
+        run_checked(
+            window,
+            "nodejs",
+            "nvm install 22",
+            node_env
+                .nvm_shell("nvm install 22 && nvm use 22 && nvm alias default 22")
+                .timeout(DOWNLOAD_TIMEOUT)
+                .cancel_token(cancel),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+struct Npm;
+
+#[async_trait]
+impl Dependency for Npm {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "npm",
+            required: true,
+            version: version::configured_minimum("npm", "10+"),
+            depends_on: vec!["nodejs"],
+            install_command: None, // npm 随 Node.js 安装
+        }
+    }
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection {
+        match probe(node_env.command("npm").arg("--version")).await {
+            Some(stdout) => Detection::found(stdout.trim()),
+            None => Detection::missing(),
+        }
+    }
+}
+
+struct Corepack;
+
+#[async_trait]
+impl Dependency for Corepack {
+    fn name(&self) -> &'static str {
+        "corepack"
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "Corepack",
+            required: false,
+            version: version::configured_minimum("corepack", "any"),
+            depends_on: vec!["nodejs", "npm"],
+            // Node.js 16.9–24 自带 corepack，Node.js 25 起需要单独安装
+            install_command: Some("npm install -g corepack".to_string()),
+        }
+    }
+
+    fn verify_hint(&self) -> &'static str {
+        "npm 全局目录的 bin（如 ~/.npm-global/bin）可能不在 PATH 中"
+    }
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection {
+        semver_detection(probe(node_env.command("corepack").arg("--version")).await)
+    }
+
+    async fn install(
+        &self,
+        window: &Window,
+        journal: &mut InstallJournal,
+        cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        let node_env = NodeEnvironment::resolve();
+
+        #[cfg(target_os = "linux")]
+        if node_env.manager == crate::node_env::NodeManager::System {
+            crate::installer::configure_npm_prefix(window, &node_env, journal, cancel).await?;
+        }
+
+        emit_progress(window, "npm install -g corepack", "running", 0.0, vec![]);
+        journal.record(UndoAction::Command {
+            program: "npm".to_string(),
+            args: vec!["uninstall".to_string(), "-g".to_string(), "corepack".to_string()],
+            cwd: None,
+        });
+        run_checked(
+            window,
+            "corepack",
+            "npm install -g corepack",
+            node_env
+                .command("npm")
+                .args(["install", "-g", "corepack"])
+                .timeout(DOWNLOAD_TIMEOUT)
+                .cancel_token(cancel),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+struct Pnpm;
+
+#[async_trait]
+impl Dependency for Pnpm {
+    fn name(&self) -> &'static str {
+        "pnpm"
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "pnpm",
+            required: false, // 只有源码安装需要
+            version: version::configured_minimum("pnpm", "9+"),
+            depends_on: vec!["corepack"],
+            install_command: Some("corepack enable pnpm".to_string()),
+        }
+    }
+
+    fn verify_hint(&self) -> &'static str {
+        "corepack 没有在 PATH 中生成 pnpm，请确认 node 的 bin 目录或 ~/.npm-global/bin 可写"
+    }
+
+    async fn detect(&self, node_env: &NodeEnvironment) -> Detection {
+        semver_detection(probe(git_source::pnpm(node_env).arg("--version")).await)
+    }
+
+    async fn install(
+        &self,
+        window: &Window,
+        journal: &mut InstallJournal,
+        cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        let node_env = git_source::ensure_pnpm(window, &NodeEnvironment::resolve(), journal, cancel).await?;
+        let output = git_source::pnpm(&node_env)
+            .arg("--version")
+            .timeout(SHORT_TIMEOUT)
+            .cancel_token(cancel)
+            .output()
+            .await?;
+
+        emit_progress(window, "pnpm 安装成功", "success", 0.0, vec![
+            format!("pnpm {}", output.stdout.trim()),
+        ]);
+        Ok(())
+    }
+}
+
+struct Git;
+
+#[async_trait]
+impl Dependency for Git {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        #[cfg(target_os = "macos")]
+        let install_command = "xcode-select --install";
+        #[cfg(target_os = "windows")]
+        let install_command = "winget install --id Git.Git -e";
+        #[cfg(not(target_os = "linux"))]
+        let install_command = Some(install_command.to_string());
+        // 按发行版选择包管理器；找不到时不提供安装命令
+        #[cfg(target_os = "linux")]
+        let install_command = platform::package_manager().map(|manager| manager.install_command(&["git"]));
+
+        Requirements {
+            display_name: "Git",
+            required: false,
+            version: version::configured_minimum("git", "2.0+"),
+            depends_on: vec![],
+            install_command,
+        }
+    }
+
+    fn verify_hint(&self) -> &'static str {
+        if cfg!(target_os = "macos") {
+            "git 由 Xcode Command Line Tools 提供，请在系统弹窗中完成安装后重新检测"
+        } else if cfg!(target_os = "windows") {
+            "winget 安装的 Git 需要重新打开安装器后才会出现在 PATH 中"
+        } else {
+            "git 已安装但不在 PATH 中，请检查包管理器的输出"
+        }
+    }
+
+    async fn detect(&self, _node_env: &NodeEnvironment) -> Detection {
+        // 格式如 "git version 2.39.3 (Apple Git-145)"
+        match probe(CommandExecutor::new("git").arg("--version")).await {
+            Some(stdout) => Detection::found(
+                SemVer::parse(&stdout)
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "Unknown".to_string()),
+            ),
+            None => Detection::missing(),
+        }
+    }
+
+    async fn install(
+        &self,
+        window: &Window,
+        _journal: &mut InstallJournal,
+        cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        #[cfg(target_os = "macos")]
+        {
+            emit_progress(window, "通过 Xcode Command Line Tools 安装 Git", "running", 0.0, vec![]);
+
+            let _output = run_logged(
+                window,
+                "git",
+                CommandExecutor::new("xcode-select")
+                    .arg("--install")
+                    .timeout(SHORT_TIMEOUT)
+                    .cancel_token(cancel),
+            )
+            .await?;
+
+            // xcode-select --install 会弹出 GUI 安装对话框
+            emit_progress(window, "已触发 Xcode Command Line Tools 安装窗口，请按照提示操作", "running", 0.0, vec![]);
+            Ok(())
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            emit_progress(window, "通过 winget 安装 Git", "running", 0.0, vec![]);
+
+            // 安装到系统目录需要管理员权限，由 UAC 确认
+            let args = ["install", "--id", "Git.Git", "-e", "--silent", "--accept-package-agreements", "--accept-source-agreements"]
+                .map(String::from);
+            crate::privilege::run_elevated(window, "git", "winget", &args, DOWNLOAD_TIMEOUT, cancel).await?;
+
+            Ok(())
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            emit_progress(window, "通过系统包管理器安装 Git", "running", 0.0, vec![]);
+            platform::install_packages(window, "git", &["git"], cancel).await?;
+
+            Ok(())
+        }
+    }
+}
+
+struct XcodeTools;
+
+#[async_trait]
+impl Dependency for XcodeTools {
+    fn name(&self) -> &'static str {
+        "xcode-tools"
+    }
+
+    fn applies(&self) -> bool {
+        cfg!(target_os = "macos")
+    }
+
+    fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+        Requirements {
+            display_name: "Xcode Command Line Tools",
+            required: false,
+            version: "any".to_string(),
+            depends_on: vec![],
+            install_command: Some("xcode-select --install".to_string()),
+        }
+    }
+
+    fn verify_hint(&self) -> &'static str {
+        "请在系统弹窗中完成 Xcode Command Line Tools 的安装，然后重新检测"
+    }
+
+    async fn detect(&self, _node_env: &NodeEnvironment) -> Detection {
+        // 检测 Xcode Command Line Tools 是否安装
+        if probe(CommandExecutor::new("xcode-select").arg("-p")).await.is_none() {
+            return Detection::missing();
+        }
+
+        // 提取版本号，格式如 "xcode-select version 2384."
+        let version = probe(CommandExecutor::new("xcode-select").arg("--version"))
+            .await
+            .and_then(|stdout| {
+                stdout
+                    .split_whitespace()
+                    .nth(2)
+                    .map(|ver| format!("installed ({})", ver.trim_end_matches('.')))
+            })
+            .unwrap_or_else(|| "installed".to_string());
+        Detection::found(version)
+    }
+
+    async fn install(
+        &self,
+        window: &Window,
+        _journal: &mut InstallJournal,
+        cancel: &CancellationToken,
+    ) -> InstallerResult<()> {
+        emit_progress(window, "正在触发 Xcode Command Line Tools 安装", "running", 0.0, vec![
+            "这将打开系统安装对话框...".to_string(),
+        ]);
+
+        let _output = run_logged(
+            window,
+            "xcode-tools",
+            CommandExecutor::new("xcode-select")
+                .arg("--install")
+                .timeout(SHORT_TIMEOUT)
+                .cancel_token(cancel),
+        )
+        .await?;
+
+        emit_progress(window, "安装窗口已打开", "running", 0.0, vec![
+            "✓ 已触发 Xcode Command Line Tools 安装对话框".to_string(),
+            "请在系统弹窗中点击【安装】按钮".to_string(),
+            "安装完成后可能需要几分钟".to_string(),
+        ]);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fake {
+        name: &'static str,
+        depends_on: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl Dependency for Fake {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn requirements(&self, _node_env: &NodeEnvironment) -> Requirements {
+            Requirements {
+                display_name: self.name,
+                required: true,
+                version: "any".to_string(),
+                depends_on: self.depends_on.clone(),
+                install_command: None,
+            }
+        }
+
+        async fn detect(&self, _node_env: &NodeEnvironment) -> Detection {
+            Detection::missing()
+        }
+    }
+
+    fn fakes(edges: &[(&'static str, &[&'static str])]) -> Vec<Box<dyn Dependency>> {
+        edges
+            .iter()
+            .map(|(name, depends_on)| {
+                Box::new(Fake {
+                    name,
+                    depends_on: depends_on.to_vec(),
+                }) as Box<dyn Dependency>
+            })
+            .collect()
+    }
+
+    fn names(order: InstallerResult<Vec<Box<dyn Dependency>>>) -> Vec<&'static str> {
+        order.unwrap().iter().map(|dep| dep.name()).collect()
+    }
+
+    #[test]
+    fn orders_prerequisites_first() {
+        let node_env = NodeEnvironment::resolve();
+        assert_eq!(
+            names(install_order(&["pnpm"], &node_env)),
+            ["nvm", "nodejs", "npm", "corepack", "pnpm"]
+        );
+        // 共同的前置依赖只出现一次，OpenClaw 排在最后
+        assert_eq!(
+            names(install_order(&["openclaw", "pnpm"], &node_env)),
+            ["nvm", "nodejs", "npm", "corepack", "pnpm", "openclaw"]
+        );
+        assert_eq!(
+            names(install_order(&["openclaw", "nodejs"], &node_env)),
+            ["nvm", "nodejs", "npm", "openclaw"]
+        );
+    }
+
+    #[test]
+    fn skips_prerequisites_missing_from_registry() {
+        let deps = fakes(&[("a", &["b", "not-on-this-platform"]), ("b", &[])]);
+        assert_eq!(names(order_from(deps, &["a"], &NodeEnvironment::resolve())), ["b", "a"]);
+    }
+
+    #[test]
+    fn rejects_unknown_dependency() {
+        let result = install_order(&["left-pad"], &NodeEnvironment::resolve());
+        assert!(matches!(result, Err(InstallerError::UnknownDependency { name }) if name == "left-pad"));
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let deps = fakes(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+        match order_from(deps, &["a"], &NodeEnvironment::resolve()) {
+            Err(InstallerError::InvalidOption { message }) => assert!(message.contains("a → b → c → a")),
+            other => panic!("expected a cycle error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
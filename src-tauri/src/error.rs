@@ -0,0 +1,290 @@
+use regex::Regex;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::executor::{CommandOutput, ExecError};
+
+// 错误信息中保留的 stderr 末尾行数
+const STDERR_TAIL_LINES: usize = 20;
+
+static PERMISSION_DENIED_RE: OnceLock<Regex> = OnceLock::new();
+
+/// 安装器统一错误类型
+///
+/// 序列化给前端时带有机器可读的 `code`（如 `COMMAND_FAILED`）、
+/// 人类可读的 `message`，以及各变体自己的字段。
+#[derive(Debug)]
+pub enum InstallerError {
+    /// 缺少必需的依赖
+    MissingDependency { names: Vec<String> },
+    /// 未知的依赖名称
+    UnknownDependency { name: String },
+    /// 安装命令成功，但重新检测发现依赖仍未就绪
+    VerificationFailed {
+        name: String,
+        required_version: String,
+        current_version: Option<String>,
+        hint: String,
+    },
+    /// 命令执行完成但返回失败
+    CommandFailed {
+        program: String,
+        exit_code: Option<i32>,
+        stderr_tail: String,
+    },
+    /// 命令不存在（未安装或不在 PATH 中）
+    CommandNotFound { program: String },
+    /// 网络不可达（DNS 解析失败、连接超时等）
+    NetworkUnreachable { program: String, stderr_tail: String },
+    /// 没有写入权限
+    PermissionDenied { path: String },
+    /// 预检未通过（磁盘空间不足、目录不可写等）
+    PreflightFailed { messages: Vec<String> },
+    /// 需要管理员权限，但无法自动提权或用户拒绝了授权
+    ElevationRequired { command: String },
+    /// 配置文件解析失败
+    ConfigParse { path: String, line: usize },
+    /// 命令超时，已被终止
+    TimedOut { program: String, timeout_secs: u64 },
+    /// 用户取消
+    Cancelled,
+    /// 已有安装任务正在运行
+    AlreadyRunning,
+    /// 无效的安装选项
+    InvalidOption { message: String },
+    /// 当前平台不支持该操作
+    Unsupported { message: String },
+    /// 其他 I/O 错误
+    Io { message: String },
+}
+
+impl InstallerError {
+    /// 机器可读的错误码，供前端和支持脚本匹配
+    pub fn code(&self) -> &'static str {
+        match self {
+            InstallerError::MissingDependency { .. } => "MISSING_DEPENDENCY",
+            InstallerError::UnknownDependency { .. } => "UNKNOWN_DEPENDENCY",
+            InstallerError::VerificationFailed { .. } => "VERIFICATION_FAILED",
+            InstallerError::CommandFailed { .. } => "COMMAND_FAILED",
+            InstallerError::CommandNotFound { .. } => "COMMAND_NOT_FOUND",
+            InstallerError::NetworkUnreachable { .. } => "NETWORK_UNREACHABLE",
+            InstallerError::PermissionDenied { .. } => "PERMISSION_DENIED",
+            InstallerError::PreflightFailed { .. } => "PREFLIGHT_FAILED",
+            InstallerError::ElevationRequired { .. } => "ELEVATION_REQUIRED",
+            InstallerError::ConfigParse { .. } => "CONFIG_PARSE",
+            InstallerError::TimedOut { .. } => "TIMED_OUT",
+            InstallerError::Cancelled => "CANCELLED",
+            InstallerError::AlreadyRunning => "ALREADY_RUNNING",
+            InstallerError::InvalidOption { .. } => "INVALID_OPTION",
+            InstallerError::Unsupported { .. } => "UNSUPPORTED",
+            InstallerError::Io { .. } => "IO",
+        }
+    }
+
+    /// 根据失败命令的输出归类错误：网络问题、权限问题或普通的命令失败
+    pub fn from_output(program: &str, output: &CommandOutput) -> Self {
+        let stderr_tail = tail_lines(&output.stderr, STDERR_TAIL_LINES);
+
+        if is_network_error(&output.stderr) {
+            return InstallerError::NetworkUnreachable {
+                program: program.to_string(),
+                stderr_tail,
+            };
+        }
+
+        if let Some(path) = permission_denied_path(&output.stderr) {
+            return InstallerError::PermissionDenied { path };
+        }
+
+        InstallerError::CommandFailed {
+            program: program.to_string(),
+            exit_code: output.exit_code,
+            stderr_tail,
+        }
+    }
+}
+
+impl fmt::Display for InstallerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallerError::MissingDependency { names } => write!(f, "缺少依赖: {}", names.join(", ")),
+            InstallerError::UnknownDependency { name } => write!(f, "未知依赖: {}", name),
+            InstallerError::VerificationFailed { name, required_version, current_version, hint } => {
+                match current_version {
+                    Some(version) => write!(f, "{} 安装后版本为 {}，未达到要求的 {}", name, version, required_version)?,
+                    None => write!(f, "{} 安装命令已完成，但仍检测不到 {}", name, name)?,
+                }
+                if !hint.is_empty() {
+                    write!(f, "。{}", hint)?;
+                }
+                Ok(())
+            }
+            InstallerError::CommandFailed { program, exit_code, stderr_tail } => {
+                match exit_code {
+                    Some(code) => write!(f, "{} 执行失败（退出码 {}）", program, code)?,
+                    None => write!(f, "{} 执行失败", program)?,
+                }
+                if !stderr_tail.is_empty() {
+                    write!(f, ": {}", stderr_tail)?;
+                }
+                Ok(())
+            }
+            InstallerError::CommandNotFound { program } => write!(f, "找不到命令: {}", program),
+            InstallerError::NetworkUnreachable { program, .. } => {
+                write!(f, "{} 无法连接网络，请检查网络或代理设置", program)
+            }
+            InstallerError::PermissionDenied { path } => write!(f, "没有写入权限: {}", path),
+            InstallerError::PreflightFailed { messages } => write!(f, "安装前检查未通过: {}", messages.join("；")),
+            InstallerError::ElevationRequired { command } => {
+                write!(f, "需要管理员权限，未能自动获取授权。请在终端中手动运行: {}", command)
+            }
+            InstallerError::ConfigParse { path, line } => write!(f, "解析配置文件失败: {}（第 {} 行）", path, line),
+            InstallerError::TimedOut { program, timeout_secs } => {
+                write!(f, "{} 执行超时（{} 秒），已终止", program, timeout_secs)
+            }
+            InstallerError::Cancelled => write!(f, "安装已取消"),
+            InstallerError::AlreadyRunning => write!(f, "已有安装任务正在进行"),
+            InstallerError::InvalidOption { message } => write!(f, "无效的安装选项: {}", message),
+            InstallerError::Unsupported { message } => write!(f, "{}", message),
+            InstallerError::Io { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for InstallerError {}
+
+impl Serialize for InstallerError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+
+        match self {
+            InstallerError::MissingDependency { names } => {
+                map.serialize_entry("names", names)?;
+            }
+            InstallerError::UnknownDependency { name } => {
+                map.serialize_entry("name", name)?;
+            }
+            InstallerError::VerificationFailed { name, required_version, current_version, hint } => {
+                map.serialize_entry("name", name)?;
+                map.serialize_entry("requiredVersion", required_version)?;
+                map.serialize_entry("currentVersion", current_version)?;
+                map.serialize_entry("hint", hint)?;
+            }
+            InstallerError::CommandFailed { program, exit_code, stderr_tail } => {
+                map.serialize_entry("program", program)?;
+                map.serialize_entry("exitCode", exit_code)?;
+                map.serialize_entry("stderrTail", stderr_tail)?;
+            }
+            InstallerError::CommandNotFound { program } => {
+                map.serialize_entry("program", program)?;
+            }
+            InstallerError::NetworkUnreachable { program, stderr_tail } => {
+                map.serialize_entry("program", program)?;
+                map.serialize_entry("stderrTail", stderr_tail)?;
+            }
+            InstallerError::PermissionDenied { path } => {
+                map.serialize_entry("path", path)?;
+            }
+            InstallerError::PreflightFailed { messages } => {
+                map.serialize_entry("messages", messages)?;
+            }
+            InstallerError::ElevationRequired { command } => {
+                map.serialize_entry("command", command)?;
+            }
+            InstallerError::ConfigParse { path, line } => {
+                map.serialize_entry("path", path)?;
+                map.serialize_entry("line", line)?;
+            }
+            InstallerError::TimedOut { program, timeout_secs } => {
+                map.serialize_entry("program", program)?;
+                map.serialize_entry("timeoutSecs", timeout_secs)?;
+            }
+            InstallerError::Cancelled
+            | InstallerError::AlreadyRunning
+            | InstallerError::InvalidOption { .. }
+            | InstallerError::Unsupported { .. }
+            | InstallerError::Io { .. } => {}
+        }
+
+        map.end()
+    }
+}
+
+impl From<ExecError> for InstallerError {
+    fn from(err: ExecError) -> Self {
+        match err {
+            ExecError::Spawn { program, source } => match source.kind() {
+                std::io::ErrorKind::NotFound => InstallerError::CommandNotFound { program },
+                std::io::ErrorKind::PermissionDenied => InstallerError::PermissionDenied { path: program },
+                _ => InstallerError::Io {
+                    message: format!("无法启动 {}: {}", program, source),
+                },
+            },
+            ExecError::Io { program, source } => InstallerError::Io {
+                message: format!("执行 {} 时出错: {}", program, source),
+            },
+            ExecError::TimedOut { program, timeout } => InstallerError::TimedOut {
+                program,
+                timeout_secs: timeout.as_secs(),
+            },
+            ExecError::Cancelled { .. } => InstallerError::Cancelled,
+        }
+    }
+}
+
+impl From<std::io::Error> for InstallerError {
+    fn from(err: std::io::Error) -> Self {
+        InstallerError::Io {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<std::env::VarError> for InstallerError {
+    fn from(err: std::env::VarError) -> Self {
+        InstallerError::Io {
+            message: format!("无法读取环境变量: {}", err),
+        }
+    }
+}
+
+pub type InstallerResult<T> = Result<T, InstallerError>;
+
+// 辅助函数
+
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let start = lines.len().saturating_sub(count);
+    lines[start..].join("\n")
+}
+
+fn is_network_error(stderr: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "ENOTFOUND",
+        "ECONNREFUSED",
+        "ECONNRESET",
+        "ETIMEDOUT",
+        "EAI_AGAIN",
+        "Could not resolve host",
+        "Failed to connect to",
+        "Connection timed out",
+        "Network is unreachable",
+    ];
+    MARKERS.iter().any(|marker| stderr.contains(marker))
+}
+
+// 从 npm 的 EACCES 报错中提取路径，如 "EACCES: permission denied, mkdir '/usr/lib/node_modules'"
+fn permission_denied_path(stderr: &str) -> Option<String> {
+    let re = PERMISSION_DENIED_RE.get_or_init(|| Regex::new(r"permission denied, \w+ '([^']+)'").unwrap());
+    if let Some(caps) = re.captures(stderr) {
+        return Some(caps.get(1)?.as_str().to_string());
+    }
+    if stderr.contains("EACCES") {
+        return Some("unknown".to_string());
+    }
+    None
+}
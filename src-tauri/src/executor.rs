@@ -1,50 +1,222 @@
-use std::process::{Command, Stdio};
-use std::io::{BufRead, BufReader};
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
 
-pub struct CommandExecutor;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+
+pub use tokio_util::sync::CancellationToken;
+
+// 终止进程树时，SIGTERM 之后等待进程自行退出的时间
+#[cfg(unix)]
+const KILL_GRACE: Duration = Duration::from_secs(3);
+
+/// 输出来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// 命令执行结果
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// 命令执行错误
+#[derive(Debug)]
+pub enum ExecError {
+    /// 进程无法启动（命令不存在、权限不足等）
+    Spawn { program: String, source: std::io::Error },
+    /// 读取输出或等待进程时出错
+    Io { program: String, source: std::io::Error },
+    /// 超过设定的超时时间，进程已被终止
+    TimedOut { program: String, timeout: Duration },
+    /// 被取消令牌中止，进程已被终止
+    Cancelled { program: String },
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::Spawn { program, source } => write!(f, "无法启动 {}: {}", program, source),
+            ExecError::Io { program, source } => write!(f, "执行 {} 时出错: {}", program, source),
+            ExecError::TimedOut { program, timeout } => {
+                write!(f, "{} 执行超时（{} 秒），已终止", program, timeout.as_secs())
+            }
+            ExecError::Cancelled { program } => write!(f, "{} 已被取消", program),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExecError::Spawn { source, .. } | ExecError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// 异步命令执行器
+///
+/// 基于 `tokio::process`，支持超时、取消令牌，并同时读取 stdout 与 stderr。
+pub struct CommandExecutor {
+    program: String,
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    current_dir: Option<PathBuf>,
+    timeout: Option<Duration>,
+    cancel: Option<CancellationToken>,
+}
 
 impl CommandExecutor {
-    /// 执行命令并实时捕获输出
-    pub fn execute_with_output<F>(
-        program: &str,
-        args: &[&str],
-        mut on_output: F,
-    ) -> Result<bool, Box<dyn std::error::Error>>
+    /// 子进程默认带上当前的代理环境变量（见 `proxy::ProxySettings::env_vars`）
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            envs: crate::proxy::child_env(),
+            current_dir: None,
+            timeout: None,
+            cancel: None,
+        }
+    }
+
+    /// 通过 `bash -c` 执行脚本（用于需要 source nvm 等 shell 函数的场景）
+    pub fn shell(script: impl Into<String>) -> Self {
+        Self::new("bash").arg("-c").arg(script)
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn cancel_token(mut self, token: &CancellationToken) -> Self {
+        self.cancel = Some(token.clone());
+        self
+    }
+
+    /// 执行命令并收集全部输出
+    pub async fn output(self) -> Result<CommandOutput, ExecError> {
+        self.execute_with_output(|_, _| {}).await
+    }
+
+    /// 执行命令并实时捕获输出，每读到一行调用一次 `on_output`
+    ///
+    /// 超时或取消时会终止整个子进程树（包括 `bash -c` 派生出的 curl、npm 等后代进程）。
+    pub async fn execute_with_output<F>(self, mut on_output: F) -> Result<CommandOutput, ExecError>
     where
-        F: FnMut(String),
+        F: FnMut(OutputStream, &str),
     {
-        let mut child = Command::new(program)
-            .args(args)
+        let program = self.program.clone();
+        let io_err = |source| ExecError::Io { program: program.clone(), source };
+
+        let cancel = self.cancel.unwrap_or_default();
+        if cancel.is_cancelled() {
+            return Err(ExecError::Cancelled { program });
+        }
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()?;
-
-        // 读取 stdout
-        if let Some(stdout) = child.stdout.take() {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    on_output(line);
-                }
-            }
+            .kill_on_drop(true);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
         }
+        // 让子进程成为新进程组的组长，终止时可以向整个进程组发送信号
+        #[cfg(unix)]
+        command.process_group(0);
 
-        // 等待进程结束
-        let status = child.wait()?;
-        Ok(status.success())
-    }
+        let mut child = command.spawn().map_err(|source| ExecError::Spawn {
+            program: program.clone(),
+            source,
+        })?;
 
-    /// 简单执行命令
-    pub fn execute(program: &str, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
-        let output = Command::new(program)
-            .args(args)
-            .output()?;
+        let mut stdout = child.stdout.take().map(|s| line_reader(s));
+        let mut stderr = child.stderr.take().map(|s| line_reader(s));
+        let mut stdout_buf = String::new();
+        let mut stderr_buf = String::new();
 
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            Err(String::from_utf8_lossy(&output.stderr).to_string().into())
+        let timeout = self.timeout;
+        let deadline = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+
+        // 同时读取 stdout 和 stderr，避免任一管道写满导致子进程阻塞
+        while stdout.is_some() || stderr.is_some() {
+            tokio::select! {
+                line = next_line(&mut stdout) => match line.map_err(io_err)? {
+                    Some(line) => {
+                        on_output(OutputStream::Stdout, &line);
+                        push_line(&mut stdout_buf, &line);
+                    }
+                    None => stdout = None,
+                },
+                line = next_line(&mut stderr) => match line.map_err(io_err)? {
+                    Some(line) => {
+                        on_output(OutputStream::Stderr, &line);
+                        push_line(&mut stderr_buf, &line);
+                    }
+                    None => stderr = None,
+                },
+                _ = &mut deadline => return Err(timed_out(&mut child, &program, timeout).await),
+                _ = cancel.cancelled() => return Err(cancelled(&mut child, &program).await),
+            }
         }
+
+        let status = tokio::select! {
+            status = child.wait() => status.map_err(io_err)?,
+            _ = &mut deadline => return Err(timed_out(&mut child, &program, timeout).await),
+            _ = cancel.cancelled() => return Err(cancelled(&mut child, &program).await),
+        };
+
+        Ok(CommandOutput {
+            success: status.success(),
+            exit_code: status.code(),
+            stdout: stdout_buf,
+            stderr: stderr_buf,
+        })
     }
 
     /// 检查命令是否存在
@@ -52,3 +224,155 @@ impl CommandExecutor {
         which::which(command).is_ok()
     }
 }
+
+type LineReader = tokio::io::Split<BufReader<Box<dyn AsyncRead + Send + Unpin>>>;
+
+fn line_reader<R: AsyncRead + Send + Unpin + 'static>(reader: R) -> LineReader {
+    let reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(reader);
+    BufReader::new(reader).split(b'\n')
+}
+
+// 管道已关闭时永远挂起，让 select! 只等待仍然打开的那一路
+async fn next_line(reader: &mut Option<LineReader>) -> std::io::Result<Option<String>> {
+    match reader {
+        Some(reader) => Ok(reader.next_segment().await?.map(|bytes| {
+            String::from_utf8_lossy(&bytes).trim_end_matches('\r').to_string()
+        })),
+        None => std::future::pending().await,
+    }
+}
+
+fn push_line(buf: &mut String, line: &str) {
+    buf.push_str(line);
+    buf.push('\n');
+}
+
+async fn timed_out(child: &mut Child, program: &str, timeout: Option<Duration>) -> ExecError {
+    kill_process_tree(child).await;
+    ExecError::TimedOut {
+        program: program.to_string(),
+        timeout: timeout.unwrap_or_default(),
+    }
+}
+
+async fn cancelled(child: &mut Child, program: &str) -> ExecError {
+    kill_process_tree(child).await;
+    ExecError::Cancelled {
+        program: program.to_string(),
+    }
+}
+
+/// 终止子进程及其所有后代进程
+async fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // 负数 pid 表示向整个进程组发送信号
+        let group = -(pid as libc::pid_t);
+        // 先礼后兵：给 npm / git 等进程清理临时文件的机会
+        unsafe { libc::kill(group, libc::SIGTERM) };
+        let _ = tokio::time::timeout(KILL_GRACE, child.wait()).await;
+        // 组长退出后进程组里可能还有残留的后代进程
+        unsafe { libc::kill(group, libc::SIGKILL) };
+    }
+
+    #[cfg(windows)]
+    if let Some(pid) = child.id() {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+    }
+
+    let _ = child.kill().await;
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    // 进程是否还在运行（已退出但未被回收的僵尸进程视为已退出）
+    #[cfg(target_os = "linux")]
+    fn alive(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.rsplit_once(") ").is_some_and(|(_, rest)| rest.starts_with('Z')))
+            .unwrap_or(false)
+    }
+
+    #[tokio::test]
+    async fn times_out_and_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let started = Instant::now();
+
+        // 后台的 sleep 是孙进程，只杀掉 sh 的话它会继续运行
+        let result = CommandExecutor::shell(format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()))
+            .timeout(Duration::from_millis(500))
+            .output()
+            .await;
+        assert!(matches!(result, Err(ExecError::TimedOut { .. })), "{:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        #[cfg(target_os = "linux")]
+        {
+            let pid = std::fs::read_to_string(&pid_file).unwrap().trim().to_string();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while alive(&pid) && Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            assert!(!alive(&pid), "sleep {} survived the timeout", pid);
+        }
+    }
+
+    #[tokio::test]
+    async fn cancel_token_stops_running_command() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            trigger.cancel();
+        });
+        let started = Instant::now();
+
+        let result = CommandExecutor::new("sleep").arg("30").cancel_token(&cancel).output().await;
+        assert!(matches!(result, Err(ExecError::Cancelled { .. })), "{:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn cancelled_token_does_not_spawn() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = CommandExecutor::new("no-such-program").cancel_token(&cancel).output().await;
+        assert!(matches!(result, Err(ExecError::Cancelled { .. })), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_program() {
+        let result = CommandExecutor::new("openclaw-installer-no-such-program").output().await;
+        assert!(matches!(result, Err(ExecError::Spawn { .. })), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn drains_large_interleaved_output() {
+        // 两路各约 200 KB，远超管道缓冲区；只读一路会让子进程阻塞在另一路上
+        let script = "i=0; while [ $i -lt 20000 ]; do echo out$i; echo err$i >&2; i=$((i+1)); done; exit 3";
+        let mut lines = (0, 0);
+        let output = CommandExecutor::shell(script)
+            .timeout(Duration::from_secs(60))
+            .execute_with_output(|stream, _| match stream {
+                OutputStream::Stdout => lines.0 += 1,
+                OutputStream::Stderr => lines.1 += 1,
+            })
+            .await
+            .unwrap();
+
+        assert!(!output.success);
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(lines, (20000, 20000));
+        assert_eq!(output.stdout.lines().count(), 20000);
+        assert_eq!(output.stderr.lines().last(), Some("err19999"));
+    }
+}
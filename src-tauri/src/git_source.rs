@@ -0,0 +1,463 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Window;
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::{CancellationToken, CommandExecutor};
+use crate::journal::{InstallJournal, UndoAction};
+use crate::installer::{emit_progress, run_checked, BUILD_TIMEOUT, DOWNLOAD_TIMEOUT, SHORT_TIMEOUT};
+use crate::node_env::{home_dir, NodeEnvironment};
+use crate::npm_registry;
+use crate::openclaw_cli::{expand_home, OpenClawCli};
+use crate::version::CalVer;
+
+pub const REPO_URL: &str = "https://github.com/openclaw/openclaw.git";
+
+// 源码安装各步骤的日志都归到 openclaw 步骤下
+const STEP: &str = "openclaw";
+// shim 中记录源码目录的标记行，升级时据此识别源码安装
+const SHIM_MARKER: &str = "openclaw-installer shim:";
+
+/// 源码安装目录：指定路径优先，否则为 ~/.openclaw-src
+pub fn source_dir(custom_path: Option<&str>) -> PathBuf {
+    match custom_path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => PathBuf::from(expand_home(path.trim())),
+        None => home_dir().join(".openclaw-src"),
+    }
+}
+
+/// 确保 pnpm 可用，缺失时通过当前 Node.js 自带的 corepack 启用
+///
+/// 返回重新定位后的工具链（corepack 可能新建了 ~/.npm-global/bin）。
+pub async fn ensure_pnpm(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<NodeEnvironment> {
+    if node_env.which("pnpm").is_some() {
+        return Ok(node_env.clone());
+    }
+    if node_env.which("corepack").is_none() {
+        return Err(InstallerError::CommandNotFound {
+            program: "corepack".to_string(),
+        });
+    }
+
+    emit_progress(window, "通过 corepack 启用 pnpm", "running", 0.0, vec![]);
+    let mut args = vec!["pnpm".to_string()];
+    // 系统 node 的 bin 目录通常属于 root，改为把 pnpm 装到 ~/.npm-global/bin
+    if !node_env.bin_dir.as_deref().is_some_and(is_writable) {
+        let dir = npm_global_bin();
+        journal.track_new_path(&dir);
+        fs::create_dir_all(&dir)?;
+        args.extend(["--install-directory".to_string(), dir.to_string_lossy().to_string()]);
+    }
+    journal.record(UndoAction::Command {
+        program: "corepack".to_string(),
+        args: [vec!["disable".to_string()], args.clone()].concat(),
+        cwd: None,
+    });
+    let corepack = node_env.command("corepack").arg("enable").args(args);
+    run_checked(
+        window,
+        "pnpm",
+        "corepack enable pnpm",
+        corepack.timeout(SHORT_TIMEOUT).cancel_token(cancel),
+    )
+    .await?;
+
+    let node_env = NodeEnvironment::resolve();
+    if node_env.which("pnpm").is_none() {
+        return Err(InstallerError::CommandNotFound {
+            program: "pnpm".to_string(),
+        });
+    }
+    Ok(node_env)
+}
+
+/// 在该工具链下运行 pnpm；corepack 首次运行时不再询问是否下载
+pub fn pnpm(node_env: &NodeEnvironment) -> CommandExecutor {
+    node_env
+        .command("pnpm")
+        .env("COREPACK_ENABLE_DOWNLOAD_PROMPT", "0")
+}
+
+/// 准备源码目录：不存在时克隆，已是 git 仓库时拉取更新，然后切换到指定的分支、tag 或提交
+///
+/// 已有检出在未指定版本时跟随远端默认分支。
+pub async fn sync_checkout(
+    window: &Window,
+    repo: &Path,
+    git_ref: Option<&str>,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    if repo.join(".git").exists() {
+        // 只更新 OpenClaw 的源码，指定路径可能是别的项目
+        if !is_openclaw_checkout(repo) {
+            return Err(InstallerError::InvalidOption {
+                message: format!("{} 是 git 仓库但不是 OpenClaw 源码，拒绝修改", repo.display()),
+            });
+        }
+
+        emit_progress(window, "更新已有源码", "running", 0.0, vec![
+            format!("源码目录: {}", repo.display()),
+        ]);
+
+        // 回滚时切回原来的分支或提交
+        if let Some(restore_head) = head_undo(repo).await {
+            // 撤销按相反顺序执行：先切回原提交，再用原来的源码重新安装依赖并构建，
+            // 避免留下新版本的 node_modules 和构建产物；原来没有构建过时直接删除它们
+            if repo.join("node_modules").exists() {
+                for arg in ["build", "install"] {
+                    journal.record(UndoAction::Command {
+                        program: "pnpm".to_string(),
+                        args: vec![arg.to_string()],
+                        cwd: Some(repo.to_path_buf()),
+                    });
+                }
+            } else {
+                journal.track_new_path(&repo.join("node_modules"));
+                journal.track_new_path(&repo.join("dist"));
+            }
+            journal.record(restore_head);
+        }
+
+        run_checked(
+            window,
+            STEP,
+            "git fetch",
+            git(repo)
+                .args(["fetch", "--tags", "--force", "origin"])
+                .timeout(DOWNLOAD_TIMEOUT)
+                .cancel_token(cancel),
+        )
+        .await?;
+
+        return checkout(window, repo, git_ref.unwrap_or("HEAD"), cancel).await;
+    }
+
+    let occupied = fs::read_dir(repo)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if occupied {
+        return Err(InstallerError::InvalidOption {
+            message: format!("{} 已存在且不是 git 仓库", repo.display()),
+        });
+    }
+
+    emit_progress(window, "克隆 OpenClaw 源码", "running", 0.0, vec![
+        format!("目标路径: {}", repo.display()),
+    ]);
+    // 目标可能是已存在的空目录，回滚时清空克隆了一半的内容
+    journal.track_new_entries(repo);
+    run_checked(
+        window,
+        STEP,
+        "git clone",
+        CommandExecutor::new("git")
+            .args(["clone", REPO_URL])
+            .arg(repo.to_string_lossy())
+            .timeout(DOWNLOAD_TIMEOUT)
+            .cancel_token(cancel),
+    )
+    .await?;
+
+    match git_ref {
+        Some(git_ref) => checkout(window, repo, git_ref, cancel).await,
+        None => Ok(()),
+    }
+}
+
+/// 安装依赖并构建
+pub async fn build(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    repo: &Path,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    emit_progress(window, "安装依赖并构建", "running", 0.0, vec![]);
+    for (label, arg) in [("pnpm install", "install"), ("pnpm build", "build")] {
+        run_checked(
+            window,
+            STEP,
+            label,
+            pnpm(node_env)
+                .arg(arg)
+                .current_dir(repo)
+                .timeout(BUILD_TIMEOUT)
+                .cancel_token(cancel),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// 把构建出的 CLI 放到 PATH 上，返回 shim 路径
+///
+/// `pnpm link --global` 依赖 `pnpm setup` 配置的 PNPM_HOME，而该目录不在检测器使用的 PATH 中；
+/// 所以直接在 node 的 bin 目录（不可写时为 ~/.npm-global/bin）写入一个调用源码入口的 shim。
+pub fn link_cli(
+    node_env: &NodeEnvironment,
+    repo: &Path,
+    journal: &mut InstallJournal,
+) -> InstallerResult<PathBuf> {
+    let entry = repo.join(bin_entry(repo)?);
+    let node = node_env
+        .which("node")
+        .unwrap_or_else(|| PathBuf::from("node"));
+
+    let shim = shim_path(node_env);
+    if let Some(dir) = shim.parent() {
+        journal.track_new_path(dir);
+        fs::create_dir_all(dir)?;
+    }
+    journal.snapshot(&shim)?;
+    write_shim(&shim, &node, &entry, repo)?;
+    Ok(shim)
+}
+
+/// shim 的写入位置：node 的 bin 目录，不可写时为 ~/.npm-global/bin
+pub fn shim_path(node_env: &NodeEnvironment) -> PathBuf {
+    shim_path_with(node_env, is_writable)
+}
+
+/// 与 `shim_path` 相同，但只检查权限、不写入探测文件（用于安装计划）
+pub fn planned_shim_path(node_env: &NodeEnvironment) -> PathBuf {
+    shim_path_with(node_env, may_write)
+}
+
+/// 确认新安装的 openclaw 能被检测到，返回其版本
+pub async fn verify() -> InstallerResult<String> {
+    let cli = OpenClawCli::resolve()?;
+    let output = cli.run_checked(["--version"]).await?;
+    Ok(CalVer::parse(&output.stdout)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| output.stdout.trim().to_string()))
+}
+
+/// 若 openclaw 可执行文件是安装器写入的 shim，返回其指向的源码目录
+pub fn shim_source(program: &Path) -> Option<PathBuf> {
+    let content = fs::read(program).ok()?;
+    // npm 安装的是 node 脚本的符号链接，体积大；shim 只有几行
+    if content.len() > 1024 {
+        return None;
+    }
+    String::from_utf8_lossy(&content)
+        .lines()
+        .find_map(|line| line.split_once(SHIM_MARKER))
+        .map(|(_, repo)| PathBuf::from(repo.trim()))
+}
+
+/// 是否为 OpenClaw 源码仓库：有 .git，且 package.json 的 name 为 openclaw
+///
+/// nvm 的 ~/.nvm、Homebrew 的 /opt/homebrew 本身也是 git 仓库，只看 .git 会误判。
+pub fn is_openclaw_checkout(dir: &Path) -> bool {
+    dir.join(".git").exists()
+        && read_manifest(dir).is_ok_and(|manifest| manifest["name"].as_str() == Some(npm_registry::PACKAGE_NAME))
+}
+
+// 辅助函数
+
+fn git(repo: &Path) -> CommandExecutor {
+    CommandExecutor::new("git").current_dir(repo)
+}
+
+// 切回当前 HEAD 的撤销操作：在分支上时切回该分支，否则切回原提交
+async fn head_undo(repo: &Path) -> Option<UndoAction> {
+    let query = |args: &[&str]| {
+        git(repo)
+            .args(args.iter().copied())
+            .timeout(SHORT_TIMEOUT)
+            .output()
+    };
+    let args = match query(&["symbolic-ref", "--quiet", "--short", "HEAD"]).await {
+        Ok(output) if output.success => vec!["checkout".to_string(), output.stdout.trim().to_string()],
+        _ => {
+            let output = query(&["rev-parse", "HEAD"]).await.ok().filter(|output| output.success)?;
+            ["checkout", "--detach", output.stdout.trim()].map(String::from).to_vec()
+        }
+    };
+    Some(UndoAction::Command {
+        program: "git".to_string(),
+        args,
+        cwd: Some(repo.to_path_buf()),
+    })
+}
+
+// 切换到目标版本；分支名优先对应 origin 上的同名分支，保证拿到远端最新提交
+async fn checkout(
+    window: &Window,
+    repo: &Path,
+    target: &str,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    let remote = format!("origin/{}", target);
+    let is_remote_branch = git(repo)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{}^{{commit}}", remote))
+        .timeout(SHORT_TIMEOUT)
+        .cancel_token(cancel)
+        .output()
+        .await
+        .map(|output| output.success)
+        .unwrap_or(false);
+    let target = if is_remote_branch { remote } else { target.to_string() };
+
+    emit_progress(window, &format!("切换到 {}", target), "running", 0.0, vec![]);
+    run_checked(
+        window,
+        STEP,
+        "git checkout",
+        git(repo)
+            .args(["-c", "advice.detachedHead=false", "checkout", "--detach"])
+            .arg(target)
+            .timeout(SHORT_TIMEOUT)
+            .cancel_token(cancel),
+    )
+    .await?;
+    Ok(())
+}
+
+fn read_manifest(repo: &Path) -> InstallerResult<serde_json::Value> {
+    let manifest_path = repo.join("package.json");
+    let manifest = fs::read_to_string(&manifest_path)?;
+    serde_json::from_str(&manifest).map_err(|e| InstallerError::ConfigParse {
+        path: manifest_path.to_string_lossy().to_string(),
+        line: e.line(),
+    })
+}
+
+// package.json 的 bin 字段可以是字符串，也可以是 { "openclaw": "..." }
+fn bin_entry(repo: &Path) -> InstallerResult<String> {
+    let manifest_path = repo.join("package.json");
+    let manifest = read_manifest(repo)?;
+
+    let bin = &manifest["bin"];
+    bin.as_str()
+        .or_else(|| bin["openclaw"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| InstallerError::Io {
+            message: format!("{} 中没有 openclaw 的 bin 入口", manifest_path.display()),
+        })
+}
+
+fn write_shim(shim: &Path, node: &Path, entry: &Path, repo: &Path) -> InstallerResult<()> {
+    #[cfg(windows)]
+    let content = format!(
+        "@echo off\r\nrem {} {}\r\n\"{}\" \"{}\" %*\r\n",
+        SHIM_MARKER,
+        repo.display(),
+        node.display(),
+        entry.display()
+    );
+    #[cfg(not(windows))]
+    let content = format!(
+        "#!/bin/sh\n# {} {}\nexec \"{}\" \"{}\" \"$@\"\n",
+        SHIM_MARKER,
+        repo.display(),
+        node.display(),
+        entry.display()
+    );
+
+    // 先删除旧文件：npm 安装留下的是符号链接，直接写入会改掉它指向的脚本
+    let _ = fs::remove_file(shim);
+    fs::write(shim, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(shim, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+// 通过写入探测文件判断目录是否可写（只看权限位无法反映目录属主）
+pub(crate) fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(".openclaw-installer-probe");
+    let writable = fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(&probe);
+    writable
+}
+
+/// 不写入任何文件地判断目录是否可写：Unix 上用 access(2) 按当前用户检查，
+/// Windows 上只能看只读属性（不准确，实际安装时仍以 `is_writable` 为准）
+pub(crate) fn may_write(dir: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: path 是以 NUL 结尾的字符串，在调用期间有效
+        unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+    }
+    #[cfg(windows)]
+    {
+        fs::metadata(dir).is_ok_and(|meta| !meta.permissions().readonly())
+    }
+}
+
+fn shim_path_with(node_env: &NodeEnvironment, writable: fn(&Path) -> bool) -> PathBuf {
+    let dir = node_env
+        .bin_dir
+        .clone()
+        .filter(|dir| writable(dir))
+        .unwrap_or_else(npm_global_bin);
+    dir.join(if cfg!(windows) { "openclaw.cmd" } else { "openclaw" })
+}
+
+fn npm_global_bin() -> PathBuf {
+    home_dir().join(".npm-global").join("bin")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_git(repo: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn undo_args(action: Option<UndoAction>) -> Vec<String> {
+        match action {
+            Some(UndoAction::Command { program, args, .. }) if program == "git" => args,
+            other => panic!("unexpected undo action {:?}", other),
+        }
+    }
+
+    #[test]
+    fn recognizes_openclaw_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("package.json"), r#"{"name":"openclaw"}"#).unwrap();
+        assert!(!is_openclaw_checkout(dir.path()));
+
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        assert!(is_openclaw_checkout(dir.path()));
+
+        fs::write(dir.path().join("package.json"), r#"{"name":"other"}"#).unwrap();
+        assert!(!is_openclaw_checkout(dir.path()));
+    }
+
+    #[tokio::test]
+    async fn head_undo_restores_branch_or_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        run_git(repo, &["init", "--quiet", "--initial-branch", "feature"]);
+        run_git(repo, &["commit", "--quiet", "--allow-empty", "-m", "init"]);
+        assert_eq!(undo_args(head_undo(repo).await), ["checkout", "feature"]);
+
+        let head = run_git(repo, &["rev-parse", "HEAD"]);
+        run_git(repo, &["checkout", "--quiet", "--detach"]);
+        assert_eq!(undo_args(head_undo(repo).await), ["checkout", "--detach", head.as_str()]);
+    }
+}
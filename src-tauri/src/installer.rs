@@ -1,290 +1,532 @@
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::Window;
 
+use crate::error::{InstallerError, InstallerResult};
+use crate::dependency::{self, Dependency};
+use crate::detector;
+use crate::executor::{CancellationToken, CommandExecutor, CommandOutput, ExecError, OutputStream};
+use crate::git_source;
+use crate::journal::InstallJournal;
+use crate::node_env::{home_dir, NodeEnvironment};
+use crate::network;
+use crate::npm_registry;
+use crate::openclaw_cli::OpenClawCli;
+use crate::preflight;
+use crate::proxy::{self, ProxySettings};
+
+// 各类命令的超时时间
+pub(crate) const SHORT_TIMEOUT: Duration = Duration::from_secs(60);
+pub(crate) const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+pub(crate) const BUILD_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InstallOptions {
     pub method: String, // "npm" or "git"
     pub custom_path: Option<String>,
+    /// 固定安装的版本：npm 安装为版本号或 dist-tag（latest、beta），git 安装为分支、tag 或提交。
+    /// 为空时 npm 安装 latest，git 使用默认分支
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 只按安装计划发送进度事件，不执行任何命令
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 代理设置，未填写的字段从环境中自动检测
+    #[serde(default)]
+    pub proxy: Option<ProxySettings>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InstallProgress {
     pub step: String,
-    pub status: String, // "pending", "running", "success", "failed"
+    pub status: String, // "pending", "running", "success", "failed", "cancelled", "planned"（预演）
     pub progress: f32,
     pub message: String,
     pub logs: Vec<String>,
 }
 
+/// 子进程输出的单行日志，通过 `install-log` 事件实时发送
+#[derive(Debug, Serialize, Clone)]
+pub struct InstallLog {
+    pub step: String,
+    pub stream: OutputStream, // "stdout" or "stderr"
+    pub line: String,
+    pub timestamp: u64, // Unix 毫秒时间戳
+}
+
+/// 当前正在运行的安装任务（由 Tauri 托管），供 `cancel_installation` 终止
+#[derive(Default)]
+pub struct InstallJobs {
+    current: Mutex<Option<CancellationToken>>,
+}
+
+impl InstallJobs {
+    /// 登记一个新任务，同一时间只允许一个安装任务运行
+    pub fn start(&self) -> InstallerResult<CancellationToken> {
+        let mut current = self.current.lock().unwrap();
+        if current.is_some() {
+            return Err(InstallerError::AlreadyRunning);
+        }
+        let token = CancellationToken::new();
+        *current = Some(token.clone());
+        Ok(token)
+    }
+
+    pub fn finish(&self) {
+        self.current.lock().unwrap().take();
+    }
+
+    /// 取消当前任务，返回是否有任务被取消
+    pub fn cancel(&self) -> bool {
+        match self.current.lock().unwrap().as_ref() {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 pub async fn install_single_dependency(
     window: &Window,
     name: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
     emit_progress(window, &format!("正在安装 {}", name), "running", 0.0, vec![]);
 
-    match name {
-        "nvm" => install_nvm(window).await?,
-        "nodejs" => install_nodejs(window).await?,
-        "git" => install_git(window).await?,
-        "xcode-tools" => install_xcode_tools(window).await?,
-        _ => return Err(format!("未知依赖: {}", name).into()),
+    let mut journal = InstallJournal::new(window);
+    let result = install_with_prerequisites(window, name, &mut journal, cancel).await;
+    match result {
+        Ok(()) => journal.commit(),
+        Err(e) => {
+            journal.rollback(window).await;
+            return Err(e);
+        }
     }
 
     emit_progress(window, &format!("{} 安装完成", name), "success", 100.0, vec![]);
     Ok(())
 }
 
-pub async fn install_openclaw(
+// 按依赖顺序安装目标及其缺失的前置依赖（如安装 Node.js 前先装 nvm）
+async fn install_with_prerequisites(
     window: &Window,
-    options: InstallOptions,
-) -> Result<String, Box<dyn std::error::Error>> {
-    // 步骤 1: 检查依赖
-    emit_progress(window, "检查系统依赖", "running", 10.0, vec![]);
-    
-    let deps = crate::detector::check_dependencies().await?;
-    let missing: Vec<_> = deps.iter()
-        .filter(|d| d.required && (!d.installed || d.needs_update))
-        .collect();
+    name: &str,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    for dep in dependency::install_order(&[name], &NodeEnvironment::resolve())? {
+        // 目标本身总是安装（可能是在升级版本）；前置依赖已就绪时跳过
+        if dep.name() != name {
+            // 每一步都重新定位工具链，让上一步装好的命令可见
+            if dep.status(&NodeEnvironment::resolve()).await.is_ready() {
+                continue;
+            }
+        }
+        dep.install(window, journal, cancel).await?;
 
-    if !missing.is_empty() {
-        let names: Vec<_> = missing.iter().map(|d| d.display_name.as_str()).collect();
-        return Err(format!("缺少依赖: {}", names.join(", ")).into());
+        // 命令退出成功不代表可用，重新检测确认版本满足要求
+        let status = dep.verify().await?;
+        emit_progress(window, &format!("{} 已就绪", status.display_name), "running", 0.0, vec![
+            format!("✓ {} {}", status.display_name, status.current_version.unwrap_or_default()),
+        ]);
     }
+    Ok(())
+}
 
-    // 步骤 2: 安装 OpenClaw
-    emit_progress(window, "安装 OpenClaw", "running", 50.0, vec![]);
-    
-    match options.method.as_str() {
-        "npm" => install_openclaw_npm(window).await?,
-        "git" => install_openclaw_git(window, options.custom_path).await?,
-        _ => return Err("无效的安装方法".into()),
+/// 一次安装所有缺失的必需依赖和 OpenClaw（源码安装时还包括 Git 和 pnpm）
+///
+/// 按依赖顺序（nvm → Node.js → pnpm → OpenClaw）逐个安装，每一步之后重新检测，确认就绪后才继续下一个。
+/// `method` 为空时沿用已有 OpenClaw 的安装方式，没有安装过则用 npm。返回实际安装的依赖名称。
+pub async fn install_missing_dependencies(
+    window: &Window,
+    method: Option<&str>,
+    cancel: &CancellationToken,
+) -> InstallerResult<Vec<String>> {
+    let method = match method {
+        Some(method @ ("npm" | "git")) => method,
+        Some(other) => {
+            return Err(InstallerError::InvalidOption {
+                message: format!("未知的安装方法 {}", other),
+            })
+        }
+        None => crate::upgrade::detect_install_method(&NodeEnvironment::resolve(), None)
+            .map(|method| method.name())
+            .unwrap_or("npm"),
+    };
+
+    emit_progress(window, "检测缺失的依赖", "running", 0.0, vec![]);
+    let deps = detector::check_dependencies().await?;
+    let missing = |name: &str| !deps.iter().any(|d| d.name == name && d.is_ready());
+
+    let mut targets: Vec<&str> = deps
+        .iter()
+        .filter(|d| d.required && !d.is_ready())
+        .map(|d| d.name.as_str())
+        .collect();
+    if method == "git" {
+        targets.extend(["git", "pnpm"].into_iter().filter(|name| missing(name)));
+    }
+    if missing(npm_registry::PACKAGE_NAME) {
+        targets.push(npm_registry::PACKAGE_NAME);
     }
 
-    // 步骤 4: 运行 setup
-    emit_progress(window, "初始化配置", "running", 80.0, vec![]);
-    run_openclaw_setup(window).await?;
+    let pending: Vec<_> = dependency::install_order(&targets, &NodeEnvironment::resolve())?
+        .into_iter()
+        .filter(|dep| missing(dep.name()))
+        .collect();
+    if pending.is_empty() {
+        emit_progress(window, "所有依赖均已就绪", "success", 100.0, vec![]);
+        return Ok(Vec::new());
+    }
 
-    emit_progress(window, "安装完成", "success", 100.0, vec![
-        "✓ OpenClaw 已成功安装".to_string(),
-        "✓ 配置已初始化".to_string(),
-    ]);
+    let mut journal = InstallJournal::new(window);
+    let mut installed = Vec::new();
+    let result = install_each(window, &pending, method, &mut installed, &mut journal, cancel).await;
+    match result {
+        Ok(()) => journal.commit(),
+        Err(e) => {
+            journal.rollback(window).await;
+            return Err(e);
+        }
+    }
 
-    Ok("安装成功！".to_string())
+    emit_progress(window, "依赖安装完成", "success", 100.0, installed.clone());
+    Ok(installed)
 }
 
-// 安装 nvm
-async fn install_nvm(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
-    emit_progress(window, "安装 nvm (Node Version Manager)", "running", 0.0, vec![]);
-    
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg("curl -o- https://raw.githubusercontent.com/nvm-sh/nvm/v0.39.7/install.sh | bash")
-        .output()?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("安装失败: {}", error).into());
-    }
+// 依次安装并重新检测；前一步顺带装好的依赖（如随 Node.js 安装的 npm）直接跳过
+async fn install_each(
+    window: &Window,
+    pending: &[Box<dyn Dependency>],
+    method: &str,
+    installed: &mut Vec<String>,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    let total = pending.len() as f32;
+    for (i, dep) in pending.iter().enumerate() {
+        if cancel.is_cancelled() {
+            return Err(InstallerError::Cancelled);
+        }
+        let node_env = NodeEnvironment::resolve();
+        let display_name = dep.requirements(&node_env).display_name;
+        if dep.status(&node_env).await.is_ready() {
+            continue;
+        }
 
-    emit_progress(window, "nvm 安装成功，请重启终端或运行 source ~/.nvm/nvm.sh", "success", 0.0, vec![]);
+        emit_progress(
+            window,
+            &format!("正在安装 {} ({}/{})", display_name, i + 1, pending.len()),
+            "running",
+            i as f32 / total * 100.0,
+            vec![],
+        );
+        // OpenClaw 不能单独安装，使用主流程的 npm / git 安装步骤
+        if dep.name() == npm_registry::PACKAGE_NAME {
+            match method {
+                "git" => install_openclaw_git(window, &node_env, None, None, journal, cancel).await?,
+                _ => install_openclaw_npm(window, &node_env, None, journal, cancel).await?,
+            }
+        } else {
+            dep.install(window, journal, cancel).await?;
+        }
+        let status = dep.verify().await?;
+        emit_progress(
+            window,
+            &format!("{} 已就绪", display_name),
+            "running",
+            (i + 1) as f32 / total * 100.0,
+            vec![format!("✓ {} {}", display_name, status.current_version.unwrap_or_default())],
+        );
+        installed.push(dep.name().to_string());
+    }
     Ok(())
 }
 
-// 通过 nvm 安装 Node.js
-async fn install_nodejs(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
-    emit_progress(window, "通过 nvm 安装 Node.js 22", "running", 0.0, vec![]);
-    
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg("source ~/.nvm/nvm.sh && nvm install 22 && nvm use 22 && nvm alias default 22")
-        .output()?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("安装失败: {}", error).into());
+pub async fn install_openclaw(
+    window: &Window,
+    options: InstallOptions,
+    cancel: &CancellationToken,
+) -> InstallerResult<String> {
+    if options.dry_run {
+        return crate::plan::dry_run(window, &options).await;
     }
 
-    Ok(())
+    let journal = InstallJournal::persistent(window, &options);
+    run_journaled(window, options, journal, cancel).await
 }
 
-// 安装 Xcode Command Line Tools
-#[cfg(target_os = "macos")]
-async fn install_xcode_tools(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
-    emit_progress(window, "正在触发 Xcode Command Line Tools 安装", "running", 0.0, vec![
-        "这将打开系统安装对话框...".to_string(),
-    ]);
-    
-    let _output = Command::new("xcode-select")
-        .arg("--install")
-        .output()?;
-
-    emit_progress(window, "安装窗口已打开", "success", 0.0, vec![
-        "✓ 已触发 Xcode Command Line Tools 安装对话框".to_string(),
-        "请在系统弹窗中点击【安装】按钮".to_string(),
-        "安装完成后可能需要几分钟".to_string(),
-    ]);
-    
-    Ok(())
+/// 从上次中断的位置继续安装，已完成的步骤会被跳过
+pub async fn resume_installation(
+    window: &Window,
+    cancel: &CancellationToken,
+) -> InstallerResult<String> {
+    let journal = InstallJournal::load(window).ok_or_else(|| InstallerError::InvalidOption {
+        message: "没有可以恢复的安装".to_string(),
+    })?;
+    let options = journal.state().options.clone().ok_or_else(|| InstallerError::InvalidOption {
+        message: "安装状态中缺少安装选项".to_string(),
+    })?;
+
+    emit_progress(window, "恢复上次未完成的安装", "running", 0.0, journal
+        .state()
+        .completed
+        .iter()
+        .map(|step| format!("✓ 已完成: {}", step.id))
+        .collect());
+    run_journaled(window, options, journal, cancel).await
+}
+
+// 失败或取消时按日志回滚，恢复到安装前的状态
+async fn run_journaled(
+    window: &Window,
+    options: InstallOptions,
+    mut journal: InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<String> {
+    // 之后启动的所有命令（包括回滚）都使用这份代理设置
+    proxy::configure(options.proxy.as_ref()).await;
+    let result = run_install(window, options, &mut journal, cancel).await;
+    match &result {
+        Ok(_) => journal.commit(),
+        Err(_) => {
+            journal.rollback(window).await;
+        }
+    }
+    result
 }
 
-// 安装 Git
-async fn install_git(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
-    #[cfg(target_os = "macos")]
-    {
-        emit_progress(window, "通过 Xcode Command Line Tools 安装 Git", "running", 0.0, vec![]);
-        
-        let _output = Command::new("xcode-select")
-            .arg("--install")
-            .output()?;
-
-        // xcode-select --install 会弹出 GUI 安装对话框
-        emit_progress(window, "已触发 Xcode Command Line Tools 安装窗口，请按照提示操作", "success", 0.0, vec![]);
-        Ok(())
+async fn run_install(
+    window: &Window,
+    options: InstallOptions,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<String> {
+    // 步骤 1: 检查依赖
+    emit_progress(window, "检查系统依赖", "running", 10.0, vec![]);
+    
+    let deps = detector::check_dependencies().await?;
+    let missing: Vec<_> = deps.iter()
+        .filter(|d| d.required && (!d.installed || d.needs_update))
+        .collect();
+
+    if !missing.is_empty() {
+        let names = missing.iter().map(|d| d.display_name.clone()).collect();
+        return Err(InstallerError::MissingDependency { names });
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        emit_progress(window, "通过 winget 安装 Git", "running", 0.0, vec![]);
-        
-        let output = Command::new("winget")
-            .args(&["install", "Git.Git"])
-            .output()?;
-
-        if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("安装失败: {}", error).into());
+    if options.method == "git" {
+        let names = detector::missing_for_git_install(&deps);
+        if !names.is_empty() {
+            return Err(InstallerError::MissingDependency { names });
         }
+    }
 
-        Ok(())
+    // 网络被拦截时 curl / npm 的报错不会说明是哪个地址，提前逐个检测
+    let endpoints = network::required_endpoints(&NodeEnvironment::resolve(), &options.method).await;
+    network::ensure_reachable(endpoints).await?;
+
+    // 磁盘写满或目录不可写时 npm 的报错很难看懂，提前检查
+    let source_dir = preflight::source_dir_for(&options.method, options.custom_path.as_deref());
+    let messages = preflight::failures(&preflight::run_checks(source_dir.as_deref()).await);
+    if !messages.is_empty() {
+        return Err(InstallerError::PreflightFailed { messages });
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        emit_progress(window, "通过 apt 安装 Git", "running", 0.0, vec![]);
-        
-        let output = Command::new("sudo")
-            .args(&["apt-get", "install", "-y", "git"])
-            .output()?;
-
-        if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("安装失败: {}", error).into());
+    // setup 会改写 ~/.openclaw 下的配置，先拍快照
+    if !journal.is_completed("snapshot") {
+        let openclaw_dir = home_dir().join(".openclaw");
+        journal.snapshot(&openclaw_dir)?;
+        journal.complete("snapshot", openclaw_dir.to_string_lossy());
+    }
+
+    // 步骤 2: 安装 OpenClaw
+    emit_progress(window, "安装 OpenClaw", "running", 50.0, vec![]);
+    let node_env = NodeEnvironment::resolve();
+    let version = options.version.as_deref().map(validate_version_spec).transpose()?;
+    
+    match options.method.as_str() {
+        "npm" => install_openclaw_npm(window, &node_env, version, journal, cancel).await?,
+        "git" => install_openclaw_git(window, &node_env, options.custom_path, version, journal, cancel).await?,
+        other => {
+            return Err(InstallerError::InvalidOption {
+                message: format!("未知的安装方法 {}", other),
+            })
         }
+    }
 
-        Ok(())
+    // 步骤 4: 运行 setup
+    emit_progress(window, "初始化配置", "running", 80.0, vec![]);
+    // 重新定位工具链，让刚安装的 openclaw（以及新建的 ~/.npm-global/bin）进入 PATH
+    if !journal.is_completed("openclaw-setup") {
+        run_openclaw_setup(window, &NodeEnvironment::resolve(), cancel).await?;
+        journal.complete("openclaw-setup", "");
     }
+
+    emit_progress(window, "安装完成", "success", 100.0, vec![
+        "✓ OpenClaw 已成功安装".to_string(),
+        "✓ 配置已初始化".to_string(),
+    ]);
+
+    Ok("安装成功！".to_string())
 }
 
-// Linux: 配置 npm prefix
+// Linux: 配置 npm prefix（系统 node 的全局目录通常属于 root）
 #[cfg(target_os = "linux")]
-async fn configure_npm_prefix(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
-    use std::env;
-    
-    let home = env::var("HOME")?;
-    let npm_global = format!("{}/.npm-global", home);
+pub(crate) async fn configure_npm_prefix(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    let home = home_dir();
+    let npm_global = home.join(".npm-global").to_string_lossy().to_string();
+
+    // npm config set 会改写 ~/.npmrc
+    journal.snapshot(&home.join(".npmrc"))?;
+    journal.track_new_path(Path::new(&npm_global));
     
     // 创建目录
     std::fs::create_dir_all(&npm_global)?;
     
     // 设置 npm prefix
-    let output = Command::new("npm")
-        .args(&["config", "set", "prefix", &npm_global])
-        .output()?;
-
-    if !output.status.success() {
-        return Err("配置 npm prefix 失败".into());
+    let output = run_logged(
+        window,
+        "npm-prefix",
+        node_env
+            .command("npm")
+            .args(["config", "set", "prefix", &npm_global])
+            .timeout(SHORT_TIMEOUT)
+            .cancel_token(cancel),
+    ).await?;
+
+    if !output.success {
+        return Err(InstallerError::from_output("npm config set prefix", &output));
     }
 
     emit_progress(window, "npm prefix 已配置", "success", 0.0, vec![
         format!("已设置: {}", npm_global),
-        "请确保 ~/.npm-global/bin 在 PATH 中".to_string(),
+        "请确保 ~/.npm-global/bin 在终端的 PATH 中".to_string(),
     ]);
 
     Ok(())
 }
 
 // 通过 npm 安装 OpenClaw
-async fn install_openclaw_npm(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
-    emit_progress(window, "npm install -g openclaw", "running", 0.0, vec![
+async fn install_openclaw_npm(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    version: Option<&str>,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    #[cfg(target_os = "linux")]
+    if node_env.manager == crate::node_env::NodeManager::System && !journal.is_completed("npm-prefix") {
+        configure_npm_prefix(window, node_env, journal, cancel).await?;
+        journal.complete("npm-prefix", home_dir().join(".npm-global").to_string_lossy());
+    }
+
+    let spec = format!("openclaw@{}", version.unwrap_or("latest"));
+    if journal.is_completed("openclaw-npm") {
+        return Ok(());
+    }
+
+    // 回滚时恢复原来的版本；原来没有安装则卸载
+    let previous = crate::upgrade::installed_version(node_env).await;
+    journal.record(crate::upgrade::npm_undo(previous.as_ref()));
+
+    emit_progress(window, &format!("npm install -g {}", spec), "running", 0.0, vec![
         "正在下载 OpenClaw...".to_string(),
     ]);
 
-    let output = Command::new("npm")
-        .args(&["install", "-g", "openclaw"])
-        .output()?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("npm 安装失败: {}", error).into());
+    let output = run_logged(
+        window,
+        "openclaw",
+        node_env
+            .command("npm")
+            .args(["install", "-g", spec.as_str()])
+            .timeout(DOWNLOAD_TIMEOUT)
+            .cancel_token(cancel),
+    ).await?;
+
+    if !output.success {
+        return Err(InstallerError::from_output("npm install -g openclaw", &output));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    emit_progress(window, "OpenClaw 安装成功", "success", 0.0, vec![
-        stdout.to_string(),
-    ]);
+    emit_progress(window, "OpenClaw 安装成功", "success", 0.0, vec![]);
+    journal.complete("openclaw-npm", spec);
 
     Ok(())
 }
 
-// 通过 git 安装 OpenClaw
+// 通过 git 源码安装 OpenClaw：克隆或更新源码、构建，并把 CLI 放到 PATH 上
 async fn install_openclaw_git(
     window: &Window,
+    node_env: &NodeEnvironment,
     custom_path: Option<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use std::env;
-    
-    let home = env::var("HOME")?;
-    let install_path = custom_path
-        .unwrap_or_else(|| format!("{}/.openclaw-src", home));
-
-    emit_progress(window, "克隆 OpenClaw 源码", "running", 0.0, vec![
-        format!("目标路径: {}", install_path),
-    ]);
+    git_ref: Option<&str>,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    let repo = git_source::source_dir(custom_path.as_deref());
+    let repo_display = repo.to_string_lossy().to_string();
+
+    let node_env = if journal.is_completed("pnpm") {
+        NodeEnvironment::resolve()
+    } else {
+        let node_env = git_source::ensure_pnpm(window, node_env, journal, cancel).await?;
+        journal.complete("pnpm", "");
+        node_env
+    };
 
-    // 克隆仓库
-    let output = Command::new("git")
-        .args(&[
-            "clone",
-            "https://github.com/openclaw/openclaw.git",
-            &install_path,
-        ])
-        .output()?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git clone 失败: {}", error).into());
+    if !journal.is_completed("git-checkout") {
+        git_source::sync_checkout(window, &repo, git_ref, journal, cancel).await?;
+        journal.complete("git-checkout", repo_display.clone());
     }
 
-    emit_progress(window, "安装依赖并构建", "running", 0.0, vec![]);
-
-    // 运行 pnpm install（假设源码使用 pnpm）
-    let output = Command::new("pnpm")
-        .args(&["install"])
-        .current_dir(&install_path)
-        .output()?;
+    if !journal.is_completed("build") {
+        git_source::build(window, &node_env, &repo, cancel).await?;
+        journal.complete("build", repo_display);
+    }
 
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("pnpm install 失败: {}", error).into());
+    if !journal.is_completed("link") {
+        let shim = git_source::link_cli(&node_env, &repo, journal)?;
+        journal.complete("link", shim.to_string_lossy());
     }
 
+    let version = git_source::verify().await?;
+    emit_progress(window, "OpenClaw 安装成功", "success", 0.0, vec![
+        format!("✓ openclaw {}", version),
+    ]);
+
     Ok(())
 }
 
 // 运行 openclaw setup
-async fn run_openclaw_setup(window: &Window) -> Result<(), Box<dyn std::error::Error>> {
+async fn run_openclaw_setup(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
     emit_progress(window, "运行 openclaw setup", "running", 0.0, vec![]);
 
-    let output = Command::new("openclaw")
-        .args(&["setup", "--non-interactive"])
-        .output()?;
+    let cli = OpenClawCli::from_env(node_env)?;
+    let output = run_logged(
+        window,
+        "openclaw-setup",
+        cli.command(["setup", "--non-interactive"])
+            .timeout(SHORT_TIMEOUT)
+            .cancel_token(cancel),
+    ).await?;
 
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
+    if !output.success {
         // setup 失败不应阻止安装完成（用户可以稍后手动运行）
         emit_progress(window, "setup 完成（部分步骤可能需要手动完成）", "success", 0.0, vec![
-            error.to_string(),
+            output.stderr,
         ]);
     } else {
         emit_progress(window, "setup 完成", "success", 0.0, vec![]);
@@ -293,8 +535,69 @@ async fn run_openclaw_setup(window: &Window) -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
+/// 校验用户指定的版本、dist-tag 或 git ref
+///
+/// 以 argv 形式传参不会被 shell 解释，但以 `-` 开头的值仍会被 npm / git 当作选项。
+pub(crate) fn validate_version_spec(spec: &str) -> InstallerResult<&str> {
+    let spec = spec.trim();
+    if spec.is_empty() || spec.starts_with('-') || spec.chars().any(char::is_whitespace) {
+        return Err(InstallerError::InvalidOption {
+            message: format!("无效的版本 {:?}", spec),
+        });
+    }
+    Ok(spec)
+}
+
+// 辅助函数：执行命令，并把每一行输出通过 install-log 事件实时发送到前端
+pub(crate) async fn run_logged(
+    window: &Window,
+    step: &str,
+    command: CommandExecutor,
+) -> Result<CommandOutput, ExecError> {
+    command
+        .execute_with_output(|stream, line| emit_log(window, step, stream, line))
+        .await
+}
+
+// 辅助函数：执行命令并实时发送输出，退出码非 0 时按 label 归类为 InstallerError
+pub(crate) async fn run_checked(
+    window: &Window,
+    step: &str,
+    label: &str,
+    command: CommandExecutor,
+) -> InstallerResult<CommandOutput> {
+    let output = run_logged(window, step, command).await?;
+    if !output.success {
+        return Err(InstallerError::from_output(label, &output));
+    }
+    Ok(output)
+}
+
+fn emit_log(window: &Window, step: &str, stream: OutputStream, line: &str) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let log = InstallLog {
+        step: step.to_string(),
+        stream,
+        line: line.to_string(),
+        timestamp,
+    };
+
+    let _ = window.emit("install-log", log);
+}
+
+// 发送最终的 cancelled 状态，前端据此退出进度页
+pub fn emit_cancelled(window: &Window) {
+    emit_progress(window, "安装已取消", "cancelled", 0.0, vec![
+        "已终止正在运行的命令".to_string(),
+    ]);
+}
+
 // 辅助函数：发送进度事件到前端
-fn emit_progress(window: &Window, step: &str, status: &str, progress: f32, logs: Vec<String>) {
+pub(crate) fn emit_progress(window: &Window, step: &str, status: &str, progress: f32, logs: Vec<String>) {
     let progress_data = InstallProgress {
         step: step.to_string(),
         status: status.to_string(),
@@ -1,55 +1,197 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod dependency;
 mod detector;
+mod error;
 mod installer;
 mod executor;
+mod git_source;
+mod journal;
+mod network;
+mod node_env;
+mod npm_registry;
+mod openclaw_cli;
+mod plan;
+mod preflight;
+mod platform;
+mod privilege;
+mod proxy;
+mod uninstall;
+mod upgrade;
+mod version;
 
-use detector::{check_dependencies, DependencyStatus};
-use installer::{install_openclaw, InstallOptions, InstallProgress};
+use detector::DependencyStatus;
+use error::{InstallerError, InstallerResult};
+use executor::CancellationToken;
+use installer::{InstallJobs, InstallOptions};
+use journal::{InstallJournal, InstallState};
+use openclaw_cli::{expand_home, OpenClawCli};
+use uninstall::{UninstallOptions, UninstallReport};
+use upgrade::{UpgradeOptions, UpgradeReport};
 
 #[tauri::command]
-async fn check_system_dependencies() -> Result<Vec<DependencyStatus>, String> {
-    detector::check_dependencies()
-        .await
-        .map_err(|e| e.to_string())
+async fn check_system_dependencies() -> InstallerResult<Vec<DependencyStatus>> {
+    detector::check_dependencies().await
 }
 
 #[tauri::command]
 async fn install_dependency(
     window: tauri::Window,
+    jobs: tauri::State<'_, InstallJobs>,
     name: String,
-) -> Result<(), String> {
-    installer::install_single_dependency(&window, &name)
-        .await
-        .map_err(|e| e.to_string())
+) -> InstallerResult<()> {
+    let cancel = jobs.start()?;
+    let result = installer::install_single_dependency(&window, &name, &cancel).await;
+    jobs.finish();
+    finish_job(&window, &cancel, result)
+}
+
+#[tauri::command]
+async fn install_missing_dependencies(
+    window: tauri::Window,
+    jobs: tauri::State<'_, InstallJobs>,
+    method: Option<String>,
+) -> InstallerResult<Vec<String>> {
+    let cancel = jobs.start()?;
+    let result = installer::install_missing_dependencies(&window, method.as_deref(), &cancel).await;
+    jobs.finish();
+    finish_job(&window, &cancel, result)
 }
 
 #[tauri::command]
 async fn start_installation(
     window: tauri::Window,
+    jobs: tauri::State<'_, InstallJobs>,
     options: InstallOptions,
-) -> Result<String, String> {
-    installer::install_openclaw(&window, options)
-        .await
-        .map_err(|e| e.to_string())
+) -> InstallerResult<String> {
+    let cancel = jobs.start()?;
+    let result = installer::install_openclaw(&window, options, &cancel).await;
+    jobs.finish();
+    finish_job(&window, &cancel, result)
+}
+
+#[tauri::command]
+async fn resume_installation(
+    window: tauri::Window,
+    jobs: tauri::State<'_, InstallJobs>,
+) -> InstallerResult<String> {
+    let cancel = jobs.start()?;
+    let result = installer::resume_installation(&window, &cancel).await;
+    jobs.finish();
+    finish_job(&window, &cancel, result)
+}
+
+/// 上次未完成的安装（应用启动时用于提示"继续安装"），没有时返回 null
+#[tauri::command]
+async fn get_install_state(window: tauri::Window) -> InstallerResult<Option<InstallState>> {
+    Ok(InstallJournal::load(&window).map(|journal| journal.state().clone()))
+}
+
+#[tauri::command]
+async fn plan_installation(options: InstallOptions) -> InstallerResult<plan::InstallPlan> {
+    plan::plan_installation(&options).await
+}
+
+#[tauri::command]
+async fn preflight_checks(
+    method: Option<String>,
+    custom_path: Option<String>,
+) -> InstallerResult<Vec<preflight::PreflightCheck>> {
+    let source_dir = preflight::source_dir_for(method.as_deref().unwrap_or("npm"), custom_path.as_deref());
+    Ok(preflight::run_checks(source_dir.as_deref()).await)
+}
+
+#[tauri::command]
+async fn get_proxy_settings() -> InstallerResult<proxy::ProxySettings> {
+    Ok(proxy::detect().await)
+}
+
+#[tauri::command]
+async fn check_network(
+    endpoints: Option<Vec<network::Endpoint>>,
+    proxy: Option<proxy::ProxySettings>,
+) -> InstallerResult<Vec<network::EndpointReport>> {
+    // 用界面上填写的代理检测，与之后的安装保持一致；诊断不改变当前生效的代理，安装可能正在进行
+    let settings = match &proxy {
+        Some(settings) => proxy::resolve(Some(settings)).await,
+        None => proxy::active(),
+    };
+    let endpoints = match endpoints {
+        Some(endpoints) => endpoints,
+        None => network::default_endpoints(&node_env::NodeEnvironment::resolve()).await,
+    };
+    Ok(network::diagnose(endpoints, &settings).await)
+}
+
+#[tauri::command]
+async fn upgrade_openclaw(
+    window: tauri::Window,
+    jobs: tauri::State<'_, InstallJobs>,
+    options: Option<UpgradeOptions>,
+) -> InstallerResult<UpgradeReport> {
+    let cancel = jobs.start()?;
+    let result = upgrade::upgrade_openclaw(&window, options.unwrap_or_default(), &cancel).await;
+    jobs.finish();
+    finish_job(&window, &cancel, result)
 }
 
 #[tauri::command]
-async fn get_system_info() -> Result<serde_json::Value, String> {
+async fn uninstall_openclaw(
+    window: tauri::Window,
+    jobs: tauri::State<'_, InstallJobs>,
+    options: Option<UninstallOptions>,
+) -> InstallerResult<UninstallReport> {
+    let cancel = jobs.start()?;
+    let result = uninstall::uninstall_openclaw(&window, options.unwrap_or_default(), &cancel).await;
+    jobs.finish();
+    finish_job(&window, &cancel, result)
+}
+
+#[tauri::command]
+async fn cancel_installation(jobs: tauri::State<'_, InstallJobs>) -> InstallerResult<bool> {
+    Ok(jobs.cancel())
+}
+
+// 任务结束后统一处理：被取消时发送 cancelled 状态
+fn finish_job<T>(
+    window: &tauri::Window,
+    cancel: &CancellationToken,
+    result: InstallerResult<T>,
+) -> InstallerResult<T> {
+    match result {
+        Err(_) if cancel.is_cancelled() => {
+            installer::emit_cancelled(window);
+            Err(InstallerError::Cancelled)
+        }
+        other => other,
+    }
+}
+
+#[tauri::command]
+async fn get_system_info() -> InstallerResult<serde_json::Value> {
+    let node_env = node_env::NodeEnvironment::resolve();
     Ok(serde_json::json!({
         "os": std::env::consts::OS,
         "arch": std::env::consts::ARCH,
-        "os_version": get_os_version(),
+        "os_version": get_os_version().await,
+        // Linux 发行版与包管理器，其他系统为 null
+        "distro": platform::linux_distro(),
+        // Linux 内核、libc、WSL 与容器环境，其他系统为 null
+        "linux": platform::linux_runtime().await,
+        // 安装系统软件包时的提权方式，null 表示只能由用户手动运行命令
+        "elevation": privilege::Elevation::detect().await,
+        "node_manager": node_env.manager,
+        "node_bin_dir": node_env.bin_dir,
     }))
 }
 
 #[tauri::command]
-async fn load_existing_config() -> Result<serde_json::Value, String> {
+async fn load_existing_config() -> InstallerResult<serde_json::Value> {
     use std::fs;
     use std::env;
     
-    let home = env::var("HOME").map_err(|_| "无法获取 HOME 目录".to_string())?;
+    let home = env::var("HOME")?;
     let config_path = format!("{}/.openclaw/openclaw.json", home);
     
     // 检查配置文件是否存在
@@ -60,11 +202,13 @@ async fn load_existing_config() -> Result<serde_json::Value, String> {
     }
     
     // 读取配置文件
-    let config_content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let config_content = fs::read_to_string(&config_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => InstallerError::PermissionDenied { path: config_path.clone() },
+        _ => InstallerError::Io { message: format!("读取配置文件失败: {}", e) },
+    })?;
     
     let config: serde_json::Value = serde_json::from_str(&config_content)
-        .map_err(|e| format!("解析配置文件失败: {}", e))?;
+        .map_err(|e| InstallerError::ConfigParse { path: config_path.clone(), line: e.line() })?;
     
     // 提取相关配置
     let mut result = serde_json::json!({
@@ -139,21 +283,16 @@ async fn load_existing_config() -> Result<serde_json::Value, String> {
 async fn install_feishu_plugin(
     app_id: String,
     app_secret: String,
-) -> Result<serde_json::Value, String> {
-    use std::process::Command;
-    
+) -> InstallerResult<serde_json::Value> {
+    let cli = OpenClawCli::resolve()?;
     let mut logs = Vec::new();
     
     // 1. 检查插件是否已安装
     logs.push("🔍 检查飞书插件状态...".to_string());
     
-    let check_output = Command::new("bash")
-        .arg("-c")
-        .arg("source ~/.nvm/nvm.sh 2>/dev/null && openclaw plugins list --json")
-        .output()
-        .map_err(|e| format!("检查插件失败: {}", e))?;
+    let check_output = cli.run(["plugins", "list", "--json"]).await?;
     
-    let plugins_output = String::from_utf8_lossy(&check_output.stdout);
+    let plugins_output = &check_output.stdout;
     let already_installed = plugins_output.contains("@openclaw/feishu") || plugins_output.contains("\"feishu\"");
     
     if already_installed {
@@ -162,14 +301,10 @@ async fn install_feishu_plugin(
         // 安装飞书插件
         logs.push("📦 正在安装 @openclaw/feishu 插件...".to_string());
         
-        let install_output = Command::new("bash")
-            .arg("-c")
-            .arg("source ~/.nvm/nvm.sh 2>/dev/null && openclaw plugins install @openclaw/feishu")
-            .output()
-            .map_err(|e| format!("执行安装命令失败: {}", e))?;
+        let install_output = cli.run(["plugins", "install", "@openclaw/feishu"]).await?;
         
-        if !install_output.status.success() {
-            let error = String::from_utf8_lossy(&install_output.stderr);
+        if !install_output.success {
+            let error = &install_output.stderr;
             // 检查是否是重复安装错误
             if error.contains("duplicate plugin id") {
                 logs.push("✅ 飞书插件已存在（检测到重复ID）".to_string());
@@ -189,25 +324,23 @@ async fn install_feishu_plugin(
     // 2. 配置飞书渠道
     logs.push("🔧 正在配置飞书渠道...".to_string());
     
-    let config_cmd = format!(
-        "source ~/.nvm/nvm.sh 2>/dev/null && openclaw config set channels.feishu.appId '{}' && openclaw config set channels.feishu.appSecret '{}'",
-        app_id, app_secret
-    );
-    
-    let config_output = Command::new("bash")
-        .arg("-c")
-        .arg(&config_cmd)
-        .output()
-        .map_err(|e| format!("执行配置命令失败: {}", e))?;
+    let feishu_settings = [
+        ("channels.feishu.appId", &app_id),
+        ("channels.feishu.appSecret", &app_secret),
+    ];
     
-    if !config_output.status.success() {
-        let error = String::from_utf8_lossy(&config_output.stderr);
-        logs.push(format!("❌ 配置失败: {}", error));
-        return Ok(serde_json::json!({
-            "success": false,
-            "error": error.to_string(),
-            "logs": logs
-        }));
+    for (key, value) in feishu_settings {
+        let config_output = cli.run(["config", "set", key, value.as_str()]).await?;
+        
+        if !config_output.success {
+            let error = &config_output.stderr;
+            logs.push(format!("❌ 配置失败: {}", error));
+            return Ok(serde_json::json!({
+                "success": false,
+                "error": error.to_string(),
+                "logs": logs
+            }));
+        }
     }
     
     logs.push("✅ 飞书凭证已保存".to_string());
@@ -215,14 +348,10 @@ async fn install_feishu_plugin(
     // 3. 重启网关
     logs.push("🔄 正在重启 OpenClaw 网关...".to_string());
     
-    let restart_output = Command::new("bash")
-        .arg("-c")
-        .arg("source ~/.nvm/nvm.sh 2>/dev/null && openclaw gateway restart")
-        .output()
-        .map_err(|e| format!("执行重启命令失败: {}", e))?;
+    let restart_output = cli.run(["gateway", "restart"]).await?;
     
-    if !restart_output.status.success() {
-        let error = String::from_utf8_lossy(&restart_output.stderr);
+    if !restart_output.success {
+        let error = &restart_output.stderr;
         logs.push(format!("⚠️ 网关重启失败: {}", error));
         logs.push("💡 请手动运行: openclaw gateway restart".to_string());
     } else {
@@ -242,12 +371,10 @@ async fn install_feishu_plugin(
 }
 
 #[tauri::command]
-async fn save_config(config: serde_json::Value) -> Result<String, String> {
-    use std::process::Command;
-    
+async fn save_config(config: serde_json::Value) -> InstallerResult<String> {
     // 提取配置值
     let mode = config["mode"].as_str().unwrap_or("local");
-    let workspace = config["workspace"].as_str().unwrap_or("~/clawd");
+    let workspace = expand_home(config["workspace"].as_str().unwrap_or("~/clawd"));
     
     // 构建 openclaw setup 命令
     let mut args = vec![
@@ -255,7 +382,7 @@ async fn save_config(config: serde_json::Value) -> Result<String, String> {
         "--non-interactive",
         "--accept-risks",
         "--mode", mode,
-        "--workspace", workspace
+        "--workspace", &workspace
     ];
     
     // 如果是远程模式，添加远程配置
@@ -273,31 +400,16 @@ async fn save_config(config: serde_json::Value) -> Result<String, String> {
     }
     
     // 执行 openclaw setup
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg(format!("source ~/.nvm/nvm.sh 2>/dev/null && openclaw {}", args.join(" ")))
-        .output()
-        .map_err(|e| format!("执行 openclaw setup 失败: {}", e))?;
-    
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("配置失败: {}", error));
-    }
+    let cli = OpenClawCli::resolve()?;
+    cli.run_checked(args).await?;
     
     // 保存 API Keys
     if let Some(api_keys) = config["apiKeys"].as_object() {
         for (provider, key) in api_keys {
             if let Some(key_str) = key.as_str() {
                 if !key_str.is_empty() {
-                    let config_cmd = format!(
-                        "source ~/.nvm/nvm.sh 2>/dev/null && openclaw config set auth.profiles.{}:default.apiKey '{}'",
-                        provider, key_str
-                    );
-                    
-                    let _ = Command::new("bash")
-                        .arg("-c")
-                        .arg(&config_cmd)
-                        .output();
+                    let profile_key = format!("auth.profiles.{}:default.apiKey", provider);
+                    let _ = cli.run(["config", "set", profile_key.as_str(), key_str]).await;
                 }
             }
         }
@@ -306,28 +418,53 @@ async fn save_config(config: serde_json::Value) -> Result<String, String> {
     Ok("配置保存成功".to_string())
 }
 
-fn get_os_version() -> String {
+async fn get_os_version() -> String {
     #[cfg(target_os = "macos")]
     {
-        use std::process::Command;
-        let output = Command::new("sw_vers")
+        use executor::CommandExecutor;
+        use std::time::Duration;
+
+        let output = CommandExecutor::new("sw_vers")
             .arg("-productVersion")
-            .output();
+            .timeout(Duration::from_secs(5))
+            .output()
+            .await;
         
         if let Ok(output) = output {
-            return String::from_utf8_lossy(&output.stdout).trim().to_string();
+            return output.stdout.trim().to_string();
         }
     }
+
+    // Linux: 发行版名称与版本，如 "Fedora Linux 40 (Workstation Edition)"
+    if let Some(distro) = platform::linux_distro() {
+        return distro.name;
+    }
     
     "Unknown".to_string()
 }
 
 fn main() {
     tauri::Builder::default()
+        .manage(InstallJobs::default())
+        .setup(|_app| {
+            // 启动时检测一次代理，依赖检测等命令无需等到安装开始
+            tauri::async_runtime::spawn(proxy::init());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             check_system_dependencies,
             install_dependency,
+            install_missing_dependencies,
             start_installation,
+            resume_installation,
+            get_install_state,
+            plan_installation,
+            preflight_checks,
+            get_proxy_settings,
+            check_network,
+            upgrade_openclaw,
+            uninstall_openclaw,
+            cancel_installation,
             get_system_info,
             load_existing_config,
             save_config,
@@ -0,0 +1,253 @@
+use serde::Serialize;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::executor::CommandExecutor;
+
+/// 管理 Node.js 的工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeManager {
+    Nvm,
+    Fnm,
+    Volta,
+    Asdf,
+    Homebrew,
+    System,
+    /// 没有找到任何 Node.js
+    None,
+}
+
+/// 当前生效的 Node.js 工具链
+///
+/// GUI 应用启动时拿不到用户 shell 里的 PATH，也无法使用 nvm 这类 shell 函数，
+/// 所以直接从文件系统定位 node 所在目录，并据此为每个子进程构造 PATH 与相关环境变量。
+/// 检测器和安装器都通过它来决定使用哪个 `node` / `npm`。
+#[derive(Debug, Clone)]
+pub struct NodeEnvironment {
+    pub manager: NodeManager,
+    /// node、npm 以及全局安装的 CLI 所在目录
+    pub bin_dir: Option<PathBuf>,
+    /// nvm 安装目录（存在 nvm.sh 时才有值）
+    pub nvm_dir: Option<PathBuf>,
+    envs: Vec<(String, String)>,
+    path: OsString,
+}
+
+impl NodeEnvironment {
+    /// 按 nvm → fnm → volta → asdf → Homebrew → 系统 的顺序定位 node
+    pub fn resolve() -> Self {
+        let home = home_dir();
+        let nvm_dir = find_nvm_dir(&home);
+
+        let detected = nvm_dir
+            .as_deref()
+            .and_then(nvm_bin_dir)
+            .map(|bin| (NodeManager::Nvm, bin, Vec::new()))
+            .or_else(|| detect_fnm(&home))
+            .or_else(|| detect_volta(&home))
+            .or_else(|| detect_asdf(&home))
+            .or_else(detect_homebrew)
+            .or_else(detect_system);
+
+        let (manager, bin_dir, mut envs) = match detected {
+            Some((manager, bin_dir, envs)) => (manager, Some(bin_dir), envs),
+            // nvm 已安装但还没有装 node 时，仍然以 nvm 作为后续安装的工具
+            None if nvm_dir.is_some() => (NodeManager::Nvm, None, Vec::new()),
+            None => (NodeManager::None, None, Vec::new()),
+        };
+
+        if let Some(nvm_dir) = &nvm_dir {
+            envs.push(("NVM_DIR".to_string(), nvm_dir.to_string_lossy().to_string()));
+        }
+
+        let mut paths: Vec<PathBuf> = bin_dir.iter().cloned().collect();
+        // Linux 使用系统 node 时，全局包安装在 ~/.npm-global（见 configure_npm_prefix）
+        let npm_global_bin = home.join(".npm-global").join("bin");
+        if npm_global_bin.is_dir() {
+            paths.push(npm_global_bin);
+        }
+        if let Some(current) = env::var_os("PATH") {
+            paths.extend(env::split_paths(&current));
+        }
+        let path = env::join_paths(paths).unwrap_or_else(|_| env::var_os("PATH").unwrap_or_default());
+        envs.push(("PATH".to_string(), path.to_string_lossy().to_string()));
+
+        Self {
+            manager,
+            bin_dir,
+            nvm_dir,
+            envs,
+            path,
+        }
+    }
+
+    /// 传给子进程的环境变量（PATH、NVM_DIR 等）
+    pub fn env_map(&self) -> &[(String, String)] {
+        &self.envs
+    }
+
+    /// 在该工具链的 PATH 中查找可执行文件
+    pub fn which(&self, program: &str) -> Option<PathBuf> {
+        let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+        which::which_in(program, Some(&self.path), cwd).ok()
+    }
+
+    /// 构建一条在该工具链下运行的命令
+    pub fn command(&self, program: &str) -> CommandExecutor {
+        let resolved = self
+            .which(program)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| program.to_string());
+        self.apply(CommandExecutor::new(resolved))
+    }
+
+    /// 在 nvm 环境中执行脚本（nvm 是 shell 函数，必须先 source nvm.sh）
+    pub fn nvm_shell(&self, script: &str) -> CommandExecutor {
+        let nvm_dir = self
+            .nvm_dir
+            .clone()
+            .unwrap_or_else(|| home_dir().join(".nvm"));
+        self.apply(CommandExecutor::shell(format!("source \"$NVM_DIR/nvm.sh\" && {}", script)))
+            .env("NVM_DIR", nvm_dir.to_string_lossy())
+    }
+
+    /// 为已有命令附加该工具链的环境变量
+    pub fn apply(&self, command: CommandExecutor) -> CommandExecutor {
+        self.env_map()
+            .iter()
+            .fold(command, |command, (key, value)| command.env(key, value))
+    }
+}
+
+// 辅助函数
+
+// 检测结果：管理工具、node 所在目录、额外的环境变量
+type Detected = (NodeManager, PathBuf, Vec<(String, String)>);
+
+/// 用户家目录（Windows 上为 USERPROFILE）
+pub fn home_dir() -> PathBuf {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+fn node_in(bin_dir: &Path) -> bool {
+    bin_dir.join(if cfg!(windows) { "node.exe" } else { "node" }).exists()
+}
+
+fn find_nvm_dir(home: &Path) -> Option<PathBuf> {
+    env::var_os("NVM_DIR")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain([home.join(".nvm"), home.join(".config").join("nvm")])
+        .find(|dir| dir.join("nvm.sh").exists())
+}
+
+// nvm 的默认版本：优先匹配 alias/default，其次选已安装的最高版本
+fn nvm_bin_dir(nvm_dir: &Path) -> Option<PathBuf> {
+    let versions_dir = nvm_dir.join("versions").join("node");
+    let mut installed: Vec<(Vec<u32>, PathBuf)> = fs::read_dir(&versions_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            Some((numeric_version(&name)?, entry.path().join("bin")))
+        })
+        .filter(|(_, bin)| node_in(bin))
+        .collect();
+    installed.sort();
+
+    let alias = fs::read_to_string(nvm_dir.join("alias").join("default")).unwrap_or_default();
+    let wanted = numeric_version(alias.trim());
+
+    let chosen = match wanted {
+        Some(prefix) => installed
+            .iter()
+            .rev()
+            .find(|(version, _)| version.starts_with(&prefix))
+            .or_else(|| installed.last()),
+        None => installed.last(),
+    };
+    chosen.map(|(_, bin)| bin.clone())
+}
+
+fn detect_fnm(home: &Path) -> Option<Detected> {
+    let candidates = env::var_os("FNM_DIR").map(PathBuf::from).into_iter().chain([
+        home.join(".local").join("share").join("fnm"),
+        home.join("Library").join("Application Support").join("fnm"),
+        home.join(".fnm"),
+    ]);
+
+    for fnm_dir in candidates {
+        let default = fnm_dir.join("aliases").join("default");
+        let bin = if cfg!(windows) { default.clone() } else { default.join("bin") };
+        if node_in(&bin) {
+            let envs = vec![("FNM_DIR".to_string(), fnm_dir.to_string_lossy().to_string())];
+            return Some((NodeManager::Fnm, bin, envs));
+        }
+    }
+    None
+}
+
+fn detect_volta(home: &Path) -> Option<Detected> {
+    let volta_home = env::var_os("VOLTA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".volta"));
+    let bin = volta_home.join("bin");
+    if !node_in(&bin) {
+        return None;
+    }
+    let envs = vec![("VOLTA_HOME".to_string(), volta_home.to_string_lossy().to_string())];
+    Some((NodeManager::Volta, bin, envs))
+}
+
+fn detect_asdf(home: &Path) -> Option<Detected> {
+    let data_dir = env::var_os("ASDF_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".asdf"));
+    let shims = data_dir.join("shims");
+    if !node_in(&shims) {
+        return None;
+    }
+    let envs = vec![("ASDF_DATA_DIR".to_string(), data_dir.to_string_lossy().to_string())];
+    Some((NodeManager::Asdf, shims, envs))
+}
+
+fn detect_homebrew() -> Option<Detected> {
+    ["/opt/homebrew/bin", "/usr/local/bin", "/home/linuxbrew/.linuxbrew/bin"]
+        .iter()
+        .map(PathBuf::from)
+        .filter(|bin| node_in(bin))
+        // /usr/local/bin 里的 node 可能来自官方 pkg 安装包，确认是 brew 管理的
+        .find(|bin| {
+            fs::canonicalize(bin.join("node"))
+                .map(|real| real.to_string_lossy().contains("Cellar"))
+                .unwrap_or(false)
+        })
+        .map(|bin| (NodeManager::Homebrew, bin, Vec::new()))
+}
+
+fn detect_system() -> Option<Detected> {
+    let node = which::which("node").ok().or_else(|| {
+        ["/usr/local/bin", "/usr/bin"]
+            .iter()
+            .map(PathBuf::from)
+            .find(|bin| node_in(bin))
+            .map(|bin| bin.join("node"))
+    })?;
+    Some((NodeManager::System, node.parent()?.to_path_buf(), Vec::new()))
+}
+
+// "v22.1.0" -> [22, 1, 0]，"22" -> [22]
+fn numeric_version(text: &str) -> Option<Vec<u32>> {
+    let parts: Option<Vec<u32>> = text
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect();
+    parts.filter(|parts| !parts.is_empty())
+}
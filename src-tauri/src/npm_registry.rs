@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::node_env::NodeEnvironment;
+use crate::proxy;
+
+pub const PACKAGE_NAME: &str = "openclaw";
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+// 查询 registry 的超时时间，网络不好时不应拖慢依赖检测
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+const NPM_CONFIG_TIMEOUT: Duration = Duration::from_secs(10);
+// 依赖检测使用的 latest 版本缓存时间；查询失败（如离线）时缓存较短，避免每次检测都等待超时
+const LATEST_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+const LATEST_FAILURE_TTL: Duration = Duration::from_secs(60);
+
+// 按 registry 地址缓存，切换镜像后不会沿用旧镜像的结果
+static LATEST_CACHE: Mutex<Option<HashMap<String, CachedLatest>>> = Mutex::new(None);
+
+// 查询时间与结果（失败为 None）
+type CachedLatest = (Instant, Option<String>);
+
+/// 确定要查询的 npm registry
+///
+/// 顺序：`OPENCLAW_INSTALLER_NPM_REGISTRY` → `npm_config_registry` → `npm config get registry`
+/// （即用户 .npmrc 中配置的企业镜像）→ 官方 registry。
+pub async fn registry_url(node_env: &NodeEnvironment) -> String {
+    let from_env = ["OPENCLAW_INSTALLER_NPM_REGISTRY", "npm_config_registry"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.trim().is_empty());
+    if let Some(url) = from_env {
+        return normalize(&url);
+    }
+
+    let from_npmrc = node_env
+        .command("npm")
+        .args(["config", "get", "registry"])
+        .timeout(NPM_CONFIG_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)
+        .map(|output| output.stdout.trim().to_string())
+        .filter(|url| url.starts_with("http"));
+
+    normalize(from_npmrc.as_deref().unwrap_or(DEFAULT_REGISTRY))
+}
+
+/// 读取 openclaw 包文档中的 dist-tags（latest、beta 等）
+pub async fn fetch_dist_tags(registry: &str) -> InstallerResult<HashMap<String, String>> {
+    let document = fetch_document(registry).await?;
+    let tags = document["dist-tags"]
+        .as_object()
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|(tag, version)| Some((tag.clone(), version.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(tags)
+}
+
+/// 查询指定版本或 dist-tag 的解压后大小（字节），用于预估下载量；registry 未提供时为 None
+pub async fn unpacked_size(node_env: &NodeEnvironment, spec: &str) -> InstallerResult<Option<u64>> {
+    let document = fetch_document(&registry_url(node_env).await).await?;
+    let version = document["dist-tags"][spec].as_str().unwrap_or(spec);
+    Ok(document["versions"][version]["dist"]["unpackedSize"].as_u64())
+}
+
+// 精简版包文档只包含安装所需字段（dist-tags、versions.*.dist 等），比完整文档小得多
+async fn fetch_document(registry: &str) -> InstallerResult<serde_json::Value> {
+    let url = format!("{}/{}", registry, PACKAGE_NAME);
+    let unreachable = |e: reqwest::Error| InstallerError::NetworkUnreachable {
+        program: registry.to_string(),
+        stderr_tail: e.to_string(),
+    };
+
+    let client = proxy::http_client(REGISTRY_TIMEOUT)?;
+
+    let response = client
+        .get(&url)
+        .header("Accept", "application/vnd.npm.install-v1+json")
+        .send()
+        .await
+        .map_err(unreachable)?;
+
+    if !response.status().is_success() {
+        return Err(InstallerError::Io {
+            message: format!("查询 {} 失败: HTTP {}", url, response.status()),
+        });
+    }
+
+    // 读取响应体失败才是网络问题；内容无法解析说明镜像返回的不是 npm 包文档
+    let body = response.bytes().await.map_err(unreachable)?;
+    serde_json::from_slice(&body).map_err(|e| InstallerError::Io {
+        message: format!("{} 返回的不是有效的 npm 包文档: {}", url, e),
+    })
+}
+
+/// 依赖检测使用的最新版本，带缓存；查询失败时为 None
+pub async fn cached_latest_version(node_env: &NodeEnvironment) -> Option<String> {
+    cached_latest(&registry_url(node_env).await).await
+}
+
+/// 查询 openclaw 的最新版本（dist-tag `latest`）
+pub async fn latest_version(node_env: &NodeEnvironment) -> InstallerResult<String> {
+    latest_from(&registry_url(node_env).await).await
+}
+
+async fn cached_latest(registry: &str) -> Option<String> {
+    if let Some((fetched_at, latest)) = LATEST_CACHE.lock().unwrap().as_ref().and_then(|cache| cache.get(registry)) {
+        let ttl = if latest.is_some() { LATEST_CACHE_TTL } else { LATEST_FAILURE_TTL };
+        if fetched_at.elapsed() < ttl {
+            return latest.clone();
+        }
+    }
+
+    let latest = latest_from(registry).await.ok();
+    LATEST_CACHE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(registry.to_string(), (Instant::now(), latest.clone()));
+    latest
+}
+
+async fn latest_from(registry: &str) -> InstallerResult<String> {
+    fetch_dist_tags(registry)
+        .await?
+        .remove("latest")
+        .ok_or_else(|| InstallerError::Io {
+            message: format!("{} 中没有 {} 的 latest 版本", registry, PACKAGE_NAME),
+        })
+}
+
+fn normalize(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // 本地替身 registry：回复一次请求，返回 registry 地址和收到的请求
+    async fn serve_once(status: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let registry = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let reply = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(reply.as_bytes()).await;
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+        (registry, server)
+    }
+
+    #[tokio::test]
+    async fn fetches_dist_tags() {
+        let (registry, server) =
+            serve_once("200 OK", r#"{"dist-tags":{"latest":"2026.2.14","beta":"2026.3.1-beta.1"}}"#).await;
+
+        let tags = fetch_dist_tags(&registry).await.unwrap();
+        assert_eq!(tags.get("latest").map(String::as_str), Some("2026.2.14"));
+        assert_eq!(tags.get("beta").map(String::as_str), Some("2026.3.1-beta.1"));
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /openclaw HTTP/1.1\r\n"), "{}", request);
+        assert!(request.to_lowercase().contains("accept: application/vnd.npm.install-v1+json"));
+    }
+
+    #[tokio::test]
+    async fn reports_http_errors() {
+        let (registry, _server) = serve_once("404 Not Found", r#"{"error":"Not found"}"#).await;
+
+        match fetch_dist_tags(&registry).await {
+            Err(InstallerError::Io { message }) => assert!(message.contains("404"), "{}", message),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_malformed_documents_as_io_errors() {
+        let (registry, _server) = serve_once("200 OK", "<html>captive portal</html>").await;
+
+        match fetch_dist_tags(&registry).await {
+            Err(InstallerError::Io { message }) => assert!(message.contains("不是有效的 npm 包文档"), "{}", message),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_refused_connections_as_unreachable() {
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+
+        let result = fetch_dist_tags(&format!("http://127.0.0.1:{}", port)).await;
+        assert!(matches!(result, Err(InstallerError::NetworkUnreachable { .. })), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn latest_version_uses_registry_override() {
+        let (registry, _server) = serve_once("200 OK", r#"{"dist-tags":{"latest":"2026.2.14"}}"#).await;
+        // 只有这个测试读取该环境变量
+        std::env::set_var("OPENCLAW_INSTALLER_NPM_REGISTRY", format!("{}/", registry));
+
+        let latest = latest_version(&NodeEnvironment::resolve()).await;
+        std::env::remove_var("OPENCLAW_INSTALLER_NPM_REGISTRY");
+        assert_eq!(latest.unwrap(), "2026.2.14");
+    }
+
+    #[tokio::test]
+    async fn caches_latest_version_per_registry() {
+        let (first, _server) = serve_once("200 OK", r#"{"dist-tags":{"latest":"2026.2.14"}}"#).await;
+        let (second, _server) = serve_once("200 OK", r#"{"dist-tags":{"latest":"2026.3.1"}}"#).await;
+
+        assert_eq!(cached_latest(&first).await.as_deref(), Some("2026.2.14"));
+        assert_eq!(cached_latest(&second).await.as_deref(), Some("2026.3.1"));
+        // 替身服务器只回复一次，再次查询命中缓存
+        assert_eq!(cached_latest(&first).await.as_deref(), Some("2026.2.14"));
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::{CommandExecutor, CommandOutput};
+use crate::node_env::NodeEnvironment;
+
+// openclaw CLI 调用（配置、插件、网关）的超时时间
+const CLI_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// openclaw CLI 调用器
+///
+/// 只定位一次 openclaw 可执行文件，之后所有调用都以 argv 形式传参，
+/// 不经过 shell 字符串，用户输入中的引号、`$`、`;` 等字符不会被解释。
+pub struct OpenClawCli {
+    program: PathBuf,
+    node_env: NodeEnvironment,
+}
+
+impl OpenClawCli {
+    /// 在当前 Node.js 工具链中定位 openclaw
+    pub fn resolve() -> InstallerResult<Self> {
+        Self::from_env(&NodeEnvironment::resolve())
+    }
+
+    /// 在指定的 Node.js 工具链中定位 openclaw（openclaw 是 node 脚本，需要同一工具链的 PATH）
+    pub fn from_env(node_env: &NodeEnvironment) -> InstallerResult<Self> {
+        let program = node_env
+            .which("openclaw")
+            .ok_or_else(|| InstallerError::CommandNotFound {
+                program: "openclaw".to_string(),
+            })?;
+
+        Ok(Self {
+            program,
+            node_env: node_env.clone(),
+        })
+    }
+
+    /// 构建一条 openclaw 命令，调用方可以继续设置超时、取消令牌等
+    pub fn command<I, S>(&self, args: I) -> CommandExecutor
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.node_env
+            .apply(CommandExecutor::new(self.program.to_string_lossy()))
+            .args(args)
+            .timeout(CLI_TIMEOUT)
+    }
+
+    /// 执行 openclaw 命令并返回输出（不检查退出码）
+    pub async fn run<I, S>(&self, args: I) -> InstallerResult<CommandOutput>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Ok(self.command(args).output().await?)
+    }
+
+    /// 执行 openclaw 命令，退出码非 0 时返回错误
+    pub async fn run_checked<I, S>(&self, args: I) -> InstallerResult<CommandOutput>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let output = self.run(args.clone()).await?;
+        if !output.success {
+            let program = format!("openclaw {}", args.first().map(String::as_str).unwrap_or_default());
+            return Err(InstallerError::from_output(program.trim_end(), &output));
+        }
+        Ok(output)
+    }
+}
+
+/// 展开开头的 `~`（以前由 shell 完成，argv 传参后需要自己处理）
+pub fn expand_home(path: &str) -> String {
+    if path == "~" || path.starts_with("~/") {
+        if let Ok(home) = std::env::var("HOME") {
+            return format!("{}{}", home, &path[1..]);
+        }
+    }
+    path.to_string()
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Window;
+
+use crate::detector::{self, DependencyStatus};
+use crate::error::{InstallerError, InstallerResult};
+use crate::git_source;
+use crate::installer::{emit_progress, validate_version_spec, InstallOptions};
+use crate::node_env::{home_dir, NodeEnvironment, NodeManager};
+use crate::npm_registry;
+use crate::preflight;
+
+const NPM_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 安装计划中的一步
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanStep {
+    /// 与 install-log 事件中的 step 一致
+    pub id: String,
+    pub description: String,
+    /// 将要执行的命令（只用于展示，不会经过 shell）
+    pub command: String,
+    /// 命令的工作目录或写入的位置
+    pub target_path: Option<String>,
+    /// openclaw 包解压后的大小（字节，不含其依赖），未知时为空
+    pub unpacked_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPlan {
+    pub method: String,
+    /// 缺少的依赖和未通过的预检；不为空时 `install_openclaw` 会直接失败
+    pub blocked_by: Vec<String>,
+    pub steps: Vec<PlanStep>,
+    pub total_unpacked_size: u64,
+}
+
+/// 根据当前依赖检测结果，列出 `install_openclaw` 将要执行的步骤
+///
+/// 只读：不执行安装命令、不写入探测文件，也不修改当前生效的代理设置（查询 registry 使用已生效的代理）。
+pub async fn plan_installation(options: &InstallOptions) -> InstallerResult<InstallPlan> {
+    if !matches!(options.method.as_str(), "npm" | "git") {
+        return Err(InstallerError::InvalidOption {
+            message: format!("未知的安装方法 {}", options.method),
+        });
+    }
+    let deps = detector::check_dependencies().await?;
+    let node_env = NodeEnvironment::resolve();
+    let version = options.version.as_deref().map(validate_version_spec).transpose()?;
+
+    let mut steps = match options.method.as_str() {
+        "git" => plan_git(&node_env, &deps, options.custom_path.as_deref(), version),
+        _ => plan_npm(&node_env, version).await,
+    };
+
+    let mut blocked_by: Vec<String> = deps
+        .iter()
+        .filter(|d| d.required && (!d.installed || d.needs_update))
+        .map(|d| d.display_name.clone())
+        .collect();
+    if options.method == "git" {
+        blocked_by.extend(detector::missing_for_git_install(&deps));
+    }
+    let source_dir = preflight::source_dir_for(&options.method, options.custom_path.as_deref());
+    blocked_by.extend(preflight::failures(&preflight::run_checks(source_dir.as_deref()).await));
+
+    steps.push(step(
+        "openclaw-setup",
+        "运行 openclaw setup",
+        "openclaw setup --non-interactive",
+        Some(home_dir().join(".openclaw")),
+        None,
+    ));
+
+    Ok(InstallPlan {
+        method: options.method.clone(),
+        blocked_by,
+        total_unpacked_size: steps.iter().filter_map(|s| s.unpacked_size).sum(),
+        steps,
+    })
+}
+
+/// 预演安装：按计划逐步发送进度事件，但不执行任何命令
+pub async fn dry_run(window: &Window, options: &InstallOptions) -> InstallerResult<String> {
+    emit_progress(window, "生成安装计划", "running", 0.0, vec![]);
+    let plan = plan_installation(options).await?;
+
+    if !plan.blocked_by.is_empty() {
+        emit_progress(window, "缺少依赖，实际安装将会失败", "planned", 0.0, plan.blocked_by.clone());
+    }
+
+    let total = plan.steps.len().max(1) as f32;
+    for (i, step) in plan.steps.iter().enumerate() {
+        let mut logs = vec![format!("$ {}", step.command)];
+        if let Some(path) = &step.target_path {
+            logs.push(format!("位置: {}", path));
+        }
+        if let Some(size) = step.unpacked_size {
+            logs.push(format!("解压后大小: {:.1} MB", size as f64 / 1_000_000.0));
+        }
+        emit_progress(window, &step.description, "planned", (i + 1) as f32 / total * 100.0, logs);
+    }
+
+    Ok(format!("预演完成，共 {} 个步骤（未执行任何命令）", plan.steps.len()))
+}
+
+// 辅助函数
+
+fn step(
+    id: &str,
+    description: &str,
+    command: impl Into<String>,
+    target_path: Option<PathBuf>,
+    unpacked_size: Option<u64>,
+) -> PlanStep {
+    PlanStep {
+        id: id.to_string(),
+        description: description.to_string(),
+        command: command.into(),
+        target_path: target_path.map(|p| p.to_string_lossy().to_string()),
+        unpacked_size,
+    }
+}
+
+async fn plan_npm(node_env: &NodeEnvironment, version: Option<&str>) -> Vec<PlanStep> {
+    let mut steps = Vec::new();
+    let npm_global = home_dir().join(".npm-global");
+
+    // 与 install_openclaw_npm 一致：Linux 系统 node 先把全局目录改到用户目录
+    let global_root = if cfg!(target_os = "linux") && node_env.manager == NodeManager::System {
+        steps.push(step(
+            "npm-prefix",
+            "配置 npm 全局目录",
+            format!("npm config set prefix {}", npm_global.display()),
+            Some(npm_global.clone()),
+            None,
+        ));
+        Some(npm_global.join("lib").join("node_modules"))
+    } else {
+        npm_global_root(node_env).await
+    };
+
+    let spec = version.unwrap_or("latest");
+    let unpacked_size = npm_registry::unpacked_size(node_env, spec).await.ok().flatten();
+    steps.push(step(
+        "openclaw",
+        "通过 npm 安装 OpenClaw",
+        format!("npm install -g {}@{}", npm_registry::PACKAGE_NAME, spec),
+        global_root.map(|root| root.join(npm_registry::PACKAGE_NAME)),
+        unpacked_size,
+    ));
+
+    steps
+}
+
+fn plan_git(
+    node_env: &NodeEnvironment,
+    deps: &[DependencyStatus],
+    custom_path: Option<&str>,
+    git_ref: Option<&str>,
+) -> Vec<PlanStep> {
+    let mut steps = Vec::new();
+    let repo = git_source::source_dir(custom_path);
+
+    if !deps.iter().any(|d| d.name == "pnpm" && d.installed) {
+        let install_dir = git_source::planned_shim_path(node_env).parent().map(PathBuf::from);
+        steps.push(step("pnpm", "通过 corepack 启用 pnpm", "corepack enable pnpm", install_dir, None));
+    }
+
+    let existing = repo.join(".git").exists();
+    if existing {
+        steps.push(step(
+            "openclaw",
+            "更新已有源码",
+            "git fetch --tags --force origin",
+            Some(repo.clone()),
+            None,
+        ));
+    } else {
+        steps.push(step(
+            "openclaw",
+            "克隆 OpenClaw 源码",
+            format!("git clone {} {}", git_source::REPO_URL, repo.display()),
+            Some(repo.clone()),
+            None,
+        ));
+    }
+
+    // 已有检出在未指定版本时跟随远端默认分支
+    if let Some(target) = git_ref.or(existing.then_some("HEAD")) {
+        steps.push(step(
+            "openclaw",
+            &format!("切换到 {}", target),
+            format!("git checkout --detach {}", target),
+            Some(repo.clone()),
+            None,
+        ));
+    }
+
+    for (command, description) in [("pnpm install", "安装依赖"), ("pnpm build", "构建")] {
+        steps.push(step("openclaw", description, command, Some(repo.clone()), None));
+    }
+
+    steps.push(step(
+        "openclaw",
+        "写入 openclaw 命令",
+        format!("write shim -> {}", repo.display()),
+        Some(git_source::planned_shim_path(node_env)),
+        None,
+    ));
+    steps.push(step("openclaw", "验证安装", "openclaw --version", None, None));
+
+    steps
+}
+
+// npm 全局包所在目录（npm root -g）
+async fn npm_global_root(node_env: &NodeEnvironment) -> Option<PathBuf> {
+    node_env
+        .command("npm")
+        .args(["root", "-g"])
+        .timeout(NPM_QUERY_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)
+        .map(|output| PathBuf::from(output.stdout.trim()))
+}
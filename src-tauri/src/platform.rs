@@ -0,0 +1,394 @@
+// 包管理器与 Linux 运行环境相关的函数只在 Linux 上被调用
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tauri::Window;
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::{CancellationToken, CommandExecutor};
+use crate::installer::DOWNLOAD_TIMEOUT;
+use crate::privilege;
+use crate::version::SemVer;
+
+// Node.js 22 官方 Linux 预编译包要求的最低 glibc 版本
+const NODE_MIN_GLIBC: &str = "2.28";
+// 探测系统信息时单条命令的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 发行版家族，决定默认的包管理器和软件包命名
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistroFamily {
+    Debian,
+    Fedora,
+    Arch,
+    Suse,
+    Alpine,
+    Unknown,
+}
+
+/// 系统包管理器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Yum,
+    Pacman,
+    Zypper,
+    Apk,
+}
+
+impl PackageManager {
+    // 没有 os-release 或家族未知时的探测顺序
+    const ALL: [PackageManager; 6] = [
+        PackageManager::Apt,
+        PackageManager::Dnf,
+        PackageManager::Yum,
+        PackageManager::Pacman,
+        PackageManager::Zypper,
+        PackageManager::Apk,
+    ];
+
+    pub fn program(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt-get",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Yum => "yum",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Apk => "apk",
+        }
+    }
+
+    /// 非交互安装软件包的参数（不含程序名）
+    pub fn install_args(self, packages: &[&str]) -> Vec<String> {
+        let base: &[&str] = match self {
+            PackageManager::Apt | PackageManager::Dnf | PackageManager::Yum => &["install", "-y"],
+            PackageManager::Pacman => &["-S", "--noconfirm", "--needed"],
+            PackageManager::Zypper => &["--non-interactive", "install"],
+            PackageManager::Apk => &["add"],
+        };
+        base.iter().chain(packages).map(|s| s.to_string()).collect()
+    }
+
+    /// 展示给用户的安装命令，如 "sudo dnf install -y git"
+    pub fn install_command(self, packages: &[&str]) -> String {
+        format!("sudo {} {}", self.program(), self.install_args(packages).join(" "))
+    }
+
+    fn is_available(self) -> bool {
+        which::which(self.program()).is_ok()
+    }
+}
+
+/// Linux 发行版信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinuxDistro {
+    /// os-release 中的 ID，如 "ubuntu"、"fedora"、"arch"
+    pub id: String,
+    /// PRETTY_NAME，如 "Fedora Linux 40 (Workstation Edition)"
+    pub name: String,
+    /// VERSION_ID，滚动发行版（Arch）没有
+    pub version: Option<String>,
+    pub family: DistroFamily,
+    pub package_manager: Option<PackageManager>,
+}
+
+/// 读取 /etc/os-release（不存在时读 /usr/lib/os-release）；非 Linux 系统返回 None
+pub fn linux_distro() -> Option<LinuxDistro> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    let fields = ["/etc/os-release", "/usr/lib/os-release"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|text| parse_os_release(&text))
+        .unwrap_or_default();
+
+    let id = fields.get("ID").cloned().unwrap_or_else(|| "linux".to_string());
+    let family = distro_family(&id, fields.get("ID_LIKE").map(String::as_str).unwrap_or(""));
+    Some(LinuxDistro {
+        name: fields
+            .get("PRETTY_NAME")
+            .or_else(|| fields.get("NAME"))
+            .cloned()
+            .unwrap_or_else(|| "Linux".to_string()),
+        version: fields.get("VERSION_ID").cloned(),
+        package_manager: detect_package_manager(family),
+        family,
+        id,
+    })
+}
+
+/// C 标准库实现，决定能否使用 Node.js 官方预编译包
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Libc {
+    Glibc,
+    Musl,
+    Unknown,
+}
+
+/// 排查 Linux 安装问题需要的运行环境信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinuxRuntime {
+    /// 内核版本（uname -r）
+    pub kernel: Option<String>,
+    pub libc: Libc,
+    pub glibc_version: Option<String>,
+    /// 是否运行在 WSL 中
+    pub wsl: bool,
+    /// 容器运行时，如 "docker"、"podman"、"lxc"、"kubernetes"
+    pub container: Option<String>,
+}
+
+/// 检测内核、libc、WSL 与容器环境；非 Linux 系统返回 None
+pub async fn linux_runtime() -> Option<LinuxRuntime> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string());
+    let glibc_version = glibc_version().await;
+    let libc = if glibc_version.is_some() {
+        Libc::Glibc
+    } else if has_musl_loader() {
+        Libc::Musl
+    } else {
+        Libc::Unknown
+    };
+
+    Some(LinuxRuntime {
+        wsl: is_wsl(kernel.as_deref()),
+        container: container_runtime(),
+        kernel,
+        libc,
+        glibc_version,
+    })
+}
+
+/// 确认当前系统能运行 Node.js 官方预编译包（nvm 安装的就是这些包）
+///
+/// 官方 Linux 包基于 glibc 构建，Node.js 22 要求 glibc 2.28 以上；musl 系统上 nvm 会退回到源码编译。
+/// 只有确认版本过低时才报错；无法确认时返回一条警告，由调用方展示后继续安装。
+pub async fn check_node_binary_support() -> InstallerResult<Option<String>> {
+    match linux_runtime().await {
+        Some(runtime) => node_binary_support(runtime.libc, runtime.glibc_version.as_deref()),
+        None => Ok(None),
+    }
+}
+
+/// 当前系统可用的包管理器
+pub fn package_manager() -> Option<PackageManager> {
+    linux_distro().and_then(|distro| distro.package_manager)
+}
+
+/// 用系统包管理器安装软件包，通过 pkexec / askpass 提权
+pub async fn install_packages(
+    window: &Window,
+    step: &str,
+    packages: &[&str],
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    let manager = package_manager().ok_or_else(|| InstallerError::Unsupported {
+        message: "未找到支持的包管理器（apt、dnf、yum、pacman、zypper、apk）".to_string(),
+    })?;
+
+    privilege::run_elevated(
+        window,
+        step,
+        manager.program(),
+        &manager.install_args(packages),
+        DOWNLOAD_TIMEOUT,
+        cancel,
+    )
+    .await?;
+    Ok(())
+}
+
+// 辅助函数
+
+fn node_binary_support(libc: Libc, glibc_version: Option<&str>) -> InstallerResult<Option<String>> {
+    let unverified = |detail: String| {
+        Ok(Some(format!(
+            "{}，无法确认能否运行 Node.js 官方预编译包（需要 glibc {} 以上），继续安装",
+            detail, NODE_MIN_GLIBC
+        )))
+    };
+
+    match (libc, glibc_version) {
+        (Libc::Musl, _) => Err(InstallerError::Unsupported {
+            message: "当前系统使用 musl libc，没有 Node.js 官方预编译包，请改用系统包管理器安装 Node.js（如 apk add nodejs npm）".to_string(),
+        }),
+        (Libc::Glibc, Some(version)) => match (SemVer::parse(version), SemVer::parse(NODE_MIN_GLIBC)) {
+            (Some(current), Some(required)) if current < required => Err(InstallerError::Unsupported {
+                message: format!(
+                    "glibc {} 低于 Node.js 22 官方预编译包要求的 {}，请升级发行版或改用系统包管理器提供的 Node.js",
+                    version, NODE_MIN_GLIBC
+                ),
+            }),
+            (Some(_), _) => Ok(None),
+            (None, _) => unverified(format!("无法解析 glibc 版本 \"{}\"", version)),
+        },
+        _ => unverified("无法确定系统的 libc 版本".to_string()),
+    }
+}
+
+// "glibc 2.35"；musl 系统上 getconf 不认识 GNU_LIBC_VERSION
+async fn glibc_version() -> Option<String> {
+    let output = CommandExecutor::new("getconf")
+        .arg("GNU_LIBC_VERSION")
+        .timeout(PROBE_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)?;
+    output.stdout.split_whitespace().nth(1).map(str::to_string)
+}
+
+// musl 的动态链接器，如 /lib/ld-musl-x86_64.so.1
+fn has_musl_loader() -> bool {
+    fs::read_dir("/lib")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.file_name().to_string_lossy().starts_with("ld-musl-"))
+        })
+        .unwrap_or(false)
+}
+
+// WSL 的内核版本带有 "microsoft"（WSL1 为 "Microsoft"，WSL2 为 "microsoft-standard-WSL2"）
+fn is_wsl(kernel: Option<&str>) -> bool {
+    std::env::var_os("WSL_DISTRO_NAME").is_some()
+        || Path::new("/proc/sys/fs/binfmt_misc/WSLInterop").exists()
+        || kernel.is_some_and(|k| k.to_lowercase().contains("microsoft"))
+}
+
+fn container_runtime() -> Option<String> {
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        return Some("kubernetes".to_string());
+    }
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    // systemd-nspawn、lxc 等会为 PID 1 设置 container 环境变量，只有 root 能读取
+    if let Some(name) = fs::read("/proc/1/environ").ok().and_then(|environ| {
+        environ
+            .split(|b| *b == 0)
+            .find_map(|var| var.strip_prefix(b"container="))
+            .map(|name| String::from_utf8_lossy(name).to_string())
+    }) {
+        return Some(name);
+    }
+    let cgroup = fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+    ["docker", "kubepods", "lxc", "containerd"]
+        .into_iter()
+        .find(|marker| cgroup.contains(marker))
+        .map(|marker| if marker == "kubepods" { "kubernetes" } else { marker }.to_string())
+}
+
+// KEY=value 格式，值可能带引号
+fn parse_os_release(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            (key.trim().to_string(), value.to_string())
+        })
+        .collect()
+}
+
+// 先看 ID，再看 ID_LIKE（如 Linux Mint 的 ID_LIKE="ubuntu debian"，Rocky 的 ID_LIKE="rhel centos fedora"）
+fn distro_family(id: &str, id_like: &str) -> DistroFamily {
+    std::iter::once(id)
+        .chain(id_like.split_whitespace())
+        .find_map(|id| match id {
+            "debian" | "ubuntu" => Some(DistroFamily::Debian),
+            "fedora" | "rhel" | "centos" => Some(DistroFamily::Fedora),
+            "arch" | "archlinux" => Some(DistroFamily::Arch),
+            "suse" | "opensuse" | "sles" => Some(DistroFamily::Suse),
+            "alpine" => Some(DistroFamily::Alpine),
+            _ if id.starts_with("opensuse") => Some(DistroFamily::Suse),
+            _ => None,
+        })
+        .unwrap_or(DistroFamily::Unknown)
+}
+
+// 优先使用发行版家族对应的包管理器，不在 PATH 中时按固定顺序探测
+fn detect_package_manager(family: DistroFamily) -> Option<PackageManager> {
+    let preferred: &[PackageManager] = match family {
+        DistroFamily::Debian => &[PackageManager::Apt],
+        // 较老的 RHEL/CentOS 只有 yum
+        DistroFamily::Fedora => &[PackageManager::Dnf, PackageManager::Yum],
+        DistroFamily::Arch => &[PackageManager::Pacman],
+        DistroFamily::Suse => &[PackageManager::Zypper],
+        DistroFamily::Alpine => &[PackageManager::Apk],
+        DistroFamily::Unknown => &[],
+    };
+
+    preferred
+        .iter()
+        .chain(PackageManager::ALL.iter())
+        .copied()
+        .find(|manager| manager.is_available())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_glibc_for_node_binaries() {
+        assert_eq!(node_binary_support(Libc::Glibc, Some("2.35")).unwrap(), None);
+        assert_eq!(node_binary_support(Libc::Glibc, Some("2.28")).unwrap(), None);
+        assert!(matches!(
+            node_binary_support(Libc::Glibc, Some("2.17")),
+            Err(InstallerError::Unsupported { .. })
+        ));
+        assert!(matches!(node_binary_support(Libc::Musl, None), Err(InstallerError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn unknown_glibc_version_only_warns() {
+        let warning = node_binary_support(Libc::Glibc, Some("unknown")).unwrap();
+        assert!(warning.is_some_and(|w| w.contains("unknown")));
+        assert!(node_binary_support(Libc::Glibc, None).unwrap().is_some());
+        assert!(node_binary_support(Libc::Unknown, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn parses_os_release() {
+        let release = parse_os_release(
+            "# comment\nNAME=\"Linux Mint\"\nVERSION_ID='21.3'\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\n\nbroken line\n",
+        );
+        assert_eq!(release.get("NAME").map(String::as_str), Some("Linux Mint"));
+        assert_eq!(release.get("VERSION_ID").map(String::as_str), Some("21.3"));
+        assert_eq!(release.get("ID_LIKE").map(String::as_str), Some("ubuntu debian"));
+        assert!(!release.contains_key("# comment"));
+    }
+
+    #[test]
+    fn maps_distro_family() {
+        assert_eq!(distro_family("ubuntu", ""), DistroFamily::Debian);
+        assert_eq!(distro_family("linuxmint", "ubuntu debian"), DistroFamily::Debian);
+        assert_eq!(distro_family("rocky", "rhel centos fedora"), DistroFamily::Fedora);
+        assert_eq!(distro_family("manjaro", "arch"), DistroFamily::Arch);
+        assert_eq!(distro_family("opensuse-tumbleweed", "opensuse suse"), DistroFamily::Suse);
+        assert_eq!(distro_family("alpine", ""), DistroFamily::Alpine);
+        assert_eq!(distro_family("nixos", ""), DistroFamily::Unknown);
+    }
+}
@@ -0,0 +1,311 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::git_source;
+use crate::node_env::{home_dir, NodeEnvironment, NodeManager};
+
+// 磁盘剩余空间低于该值时无法安装（npm 全局包解压后约数百 MB）
+const MIN_FREE_DISK: u64 = 500 * MB;
+// 低于该值时给出警告（源码安装的 node_modules 与构建产物约 1–2 GB）
+const RECOMMENDED_FREE_DISK: u64 = 2 * GB;
+const MIN_MEMORY: u64 = GB;
+// pnpm build 在内存不足 2 GB 时容易被 OOM 终止
+const RECOMMENDED_MEMORY: u64 = 2 * GB;
+const RECOMMENDED_CPUS: usize = 2;
+
+const MB: u64 = 1024 * 1024;
+const GB: u64 = 1024 * MB;
+
+// 查询系统信息时单条命令的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// 一项预检结果，字段与 `DependencyStatus` 对应，前端可以用同样的列表展示
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightCheck {
+    pub name: String,
+    pub display_name: String,
+    pub status: CheckStatus,
+    /// 要求，如 "≥ 2.0 GB"
+    pub required_value: String,
+    /// 检测到的值，检测不了时为空
+    pub current_value: Option<String>,
+    /// warn / fail 时的说明
+    pub message: Option<String>,
+}
+
+/// 检查磁盘空间、内存、CPU 以及安装目标的写入权限
+///
+/// `source_dir` 为源码安装的目录，npm 安装时传 None。
+pub async fn run_checks(source_dir: Option<&Path>) -> Vec<PreflightCheck> {
+    let node_env = NodeEnvironment::resolve();
+    let home = home_dir();
+    let npm_prefix = npm_prefix(&node_env).await;
+
+    let mut targets = vec![("home", "主目录", home.join(".openclaw"))];
+    if let Some(prefix) = &npm_prefix {
+        targets.push(("npm-prefix", "npm 全局目录", prefix.clone()));
+    }
+    if let Some(dir) = source_dir {
+        targets.push(("source", "源码目录", dir.to_path_buf()));
+    }
+
+    let mut checks = Vec::new();
+    let mut seen_volumes = Vec::new();
+    for (id, label, path) in &targets {
+        let existing = existing_ancestor(path);
+        // 同一个卷只检查一次
+        let volume = volume_id(&existing);
+        if !seen_volumes.contains(&volume) {
+            seen_volumes.push(volume);
+            checks.push(check_disk(id, label, &existing).await);
+        }
+    }
+
+    for (id, label, path) in &targets {
+        let check = match *id {
+            "npm-prefix" => check_npm_prefix(&node_env, path),
+            _ => check_writable(id, label, path),
+        };
+        checks.push(check);
+    }
+
+    checks.push(check_memory().await);
+    checks.push(check_cpus());
+    checks
+}
+
+/// 未通过的检查项说明，用于阻止安装
+pub fn failures(checks: &[PreflightCheck]) -> Vec<String> {
+    checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .map(|c| c.message.clone().unwrap_or_else(|| c.display_name.clone()))
+        .collect()
+}
+
+/// 需要检查的源码目录：源码安装时为指定路径或默认的 ~/.openclaw-src，npm 安装时为 None
+pub fn source_dir_for(method: &str, custom_path: Option<&str>) -> Option<PathBuf> {
+    (method == "git").then(|| git_source::source_dir(custom_path))
+}
+
+// 各检查项
+
+async fn check_disk(id: &str, label: &str, path: &Path) -> PreflightCheck {
+    let free = free_space(path).await;
+    let (status, message) = match free {
+        Some(free) if free < MIN_FREE_DISK => (
+            CheckStatus::Fail,
+            Some(format!("{} 所在磁盘只剩 {}，安装至少需要 {}", path.display(), format_bytes(free), format_bytes(MIN_FREE_DISK))),
+        ),
+        Some(free) if free < RECOMMENDED_FREE_DISK => (
+            CheckStatus::Warn,
+            Some(format!("{} 所在磁盘剩余空间不足 {}，源码安装可能失败", path.display(), format_bytes(RECOMMENDED_FREE_DISK))),
+        ),
+        Some(_) => (CheckStatus::Pass, None),
+        None => (CheckStatus::Warn, Some(format!("无法获取 {} 所在磁盘的剩余空间", path.display()))),
+    };
+
+    PreflightCheck {
+        name: format!("disk-{}", id),
+        display_name: format!("磁盘空间（{}）", label),
+        status,
+        required_value: format!("≥ {}", format_bytes(RECOMMENDED_FREE_DISK)),
+        current_value: free.map(format_bytes),
+        message,
+    }
+}
+
+fn check_writable(id: &str, label: &str, path: &Path) -> PreflightCheck {
+    let existing = existing_ancestor(path);
+    let writable = git_source::may_write(&existing);
+    PreflightCheck {
+        name: format!("write-{}", id),
+        display_name: format!("写入权限（{}）", label),
+        status: if writable { CheckStatus::Pass } else { CheckStatus::Fail },
+        required_value: "可写".to_string(),
+        current_value: Some(existing.to_string_lossy().to_string()),
+        message: (!writable).then(|| format!("没有 {} 的写入权限", existing.display())),
+    }
+}
+
+fn check_npm_prefix(node_env: &NodeEnvironment, prefix: &Path) -> PreflightCheck {
+    let mut check = check_writable("npm-prefix", "npm 全局目录", &npm_global_modules(prefix));
+    // Linux 系统 node 的全局目录属于 root，安装时会改用 ~/.npm-global（见 configure_npm_prefix）
+    if check.status == CheckStatus::Fail
+        && cfg!(target_os = "linux")
+        && node_env.manager == NodeManager::System
+    {
+        let fallback = home_dir().join(".npm-global");
+        if git_source::may_write(&existing_ancestor(&fallback)) {
+            check.status = CheckStatus::Pass;
+            check.message = Some(format!("安装时将改用 {}", fallback.display()));
+        }
+    }
+    check
+}
+
+async fn check_memory() -> PreflightCheck {
+    let total = total_memory().await;
+    let (status, message) = match total {
+        Some(total) if total < MIN_MEMORY => (
+            CheckStatus::Fail,
+            Some(format!("内存只有 {}，至少需要 {}", format_bytes(total), format_bytes(MIN_MEMORY))),
+        ),
+        Some(total) if total < RECOMMENDED_MEMORY => (
+            CheckStatus::Warn,
+            Some(format!("内存低于 {}，源码构建可能因内存不足失败", format_bytes(RECOMMENDED_MEMORY))),
+        ),
+        Some(_) => (CheckStatus::Pass, None),
+        None => (CheckStatus::Warn, Some("无法获取内存大小".to_string())),
+    };
+
+    PreflightCheck {
+        name: "memory".to_string(),
+        display_name: "内存".to_string(),
+        status,
+        required_value: format!("≥ {}", format_bytes(RECOMMENDED_MEMORY)),
+        current_value: total.map(format_bytes),
+        message,
+    }
+}
+
+fn check_cpus() -> PreflightCheck {
+    let cpus = std::thread::available_parallelism().map(|n| n.get()).ok();
+    let enough = !matches!(cpus, Some(n) if n < RECOMMENDED_CPUS);
+    PreflightCheck {
+        name: "cpu".to_string(),
+        display_name: "CPU 核心数".to_string(),
+        status: if enough { CheckStatus::Pass } else { CheckStatus::Warn },
+        required_value: format!("≥ {}", RECOMMENDED_CPUS),
+        current_value: cpus.map(|n| n.to_string()),
+        message: (!enough).then(|| "CPU 核心数较少，源码构建会比较慢".to_string()),
+    }
+}
+
+// 辅助函数
+
+// npm prefix -g；npm 不可用时为空（由依赖检测报告）
+async fn npm_prefix(node_env: &NodeEnvironment) -> Option<PathBuf> {
+    node_env
+        .command("npm")
+        .args(["prefix", "-g"])
+        .timeout(PROBE_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)
+        .map(|output| PathBuf::from(output.stdout.trim()))
+}
+
+// npm install -g 实际写入的目录
+fn npm_global_modules(prefix: &Path) -> PathBuf {
+    if cfg!(windows) {
+        prefix.join("node_modules")
+    } else {
+        prefix.join("lib").join("node_modules")
+    }
+}
+
+// 目标还不存在时，检查将要创建它的最近一级已存在的目录
+fn existing_ancestor(path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else {
+        format!("{} MB", bytes / MB)
+    }
+}
+
+#[cfg(unix)]
+fn volume_id(path: &Path) -> String {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.dev().to_string())
+        .unwrap_or_else(|_| path.to_string_lossy().to_string())
+}
+
+#[cfg(windows)]
+fn volume_id(path: &Path) -> String {
+    // 盘符，如 "C:\"
+    path.ancestors()
+        .last()
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_uppercase()
+}
+
+#[cfg(unix)]
+async fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: path 是以 NUL 结尾的字符串，stat 在调用期间有效
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // 字段类型因平台而异（macOS 上 f_bavail 为 u32）
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+async fn free_space(path: &Path) -> Option<u64> {
+    let root = volume_id(path).replace('\'', "''");
+    powershell_number(&format!("(New-Object System.IO.DriveInfo('{}')).AvailableFreeSpace", root)).await
+}
+
+#[cfg(target_os = "linux")]
+async fn total_memory() -> Option<u64> {
+    // "MemTotal:       16318820 kB"
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(target_os = "macos")]
+async fn total_memory() -> Option<u64> {
+    let output = crate::executor::CommandExecutor::new("sysctl")
+        .args(["-n", "hw.memsize"])
+        .timeout(PROBE_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)?;
+    output.stdout.trim().parse().ok()
+}
+
+#[cfg(windows)]
+async fn total_memory() -> Option<u64> {
+    powershell_number("(Get-CimInstance Win32_ComputerSystem).TotalPhysicalMemory").await
+}
+
+#[cfg(windows)]
+async fn powershell_number(script: &str) -> Option<u64> {
+    let output = crate::executor::CommandExecutor::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .timeout(PROBE_TIMEOUT)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.success)?;
+    output.stdout.trim().parse().ok()
+}
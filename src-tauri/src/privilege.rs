@@ -0,0 +1,216 @@
+use serde::Serialize;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Window;
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::{CancellationToken, CommandExecutor, CommandOutput};
+use crate::installer::run_logged;
+
+// 探测提权方式时单条命令的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 常见的图形化 askpass 程序（SUDO_ASKPASS 未设置时依次查找）
+#[cfg(unix)]
+const ASKPASS_HELPERS: &[&str] = &[
+    "ssh-askpass",
+    "ksshaskpass",
+    "lxqt-openssh-askpass",
+    "/usr/lib/ssh/ssh-askpass",
+    "/usr/libexec/openssh/gnome-ssh-askpass",
+    "/usr/libexec/openssh/ssh-askpass",
+    "/usr/lib/openssh/gnome-ssh-askpass",
+];
+
+/// 以管理员权限运行命令的方式
+///
+/// GUI 应用没有终端，普通的 `sudo` 无法输入密码，只能使用弹出图形化认证窗口的方式。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum Elevation {
+    /// 已经是 root / 管理员，直接运行
+    AlreadyElevated,
+    /// sudo 不需要密码（NOPASSWD 或仍在凭证缓存期内）
+    #[cfg(unix)]
+    SudoNoPassword,
+    /// polkit 的 pkexec，由桌面环境的认证代理弹出密码窗口
+    #[cfg(unix)]
+    Pkexec,
+    /// `sudo -A`，通过图形化 askpass 程序读取密码
+    #[cfg(unix)]
+    SudoAskpass { askpass: PathBuf },
+    /// Windows: PowerShell `Start-Process -Verb RunAs` 触发 UAC 确认
+    #[cfg(windows)]
+    RunAs,
+}
+
+impl Elevation {
+    /// 当前环境可用的提权方式；都不可用时返回 None，调用方应提示用户手动运行命令
+    pub async fn detect() -> Option<Elevation> {
+        #[cfg(windows)]
+        {
+            // net session 只有管理员才能执行成功
+            let admin = probe(CommandExecutor::new("net").arg("session")).await;
+            Some(if admin { Elevation::AlreadyElevated } else { Elevation::RunAs })
+        }
+
+        #[cfg(unix)]
+        {
+            // SAFETY: geteuid 没有前置条件，总是成功
+            if unsafe { libc::geteuid() } == 0 {
+                return Some(Elevation::AlreadyElevated);
+            }
+            if probe(CommandExecutor::new("sudo").args(["-n", "true"])).await {
+                return Some(Elevation::SudoNoPassword);
+            }
+
+            // 两种图形化方式都需要桌面会话
+            let graphical = std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+            if !graphical {
+                return None;
+            }
+            if which::which("pkexec").is_ok() {
+                return Some(Elevation::Pkexec);
+            }
+            if which::which("sudo").is_ok() {
+                if let Some(askpass) = find_askpass() {
+                    return Some(Elevation::SudoAskpass { askpass });
+                }
+            }
+            None
+        }
+    }
+
+    /// 包装成以管理员权限运行的命令
+    pub fn command(&self, program: &str, args: &[String]) -> CommandExecutor {
+        match self {
+            Elevation::AlreadyElevated => CommandExecutor::new(program).args(args.iter().cloned()),
+            #[cfg(unix)]
+            Elevation::SudoNoPassword => CommandExecutor::new("sudo")
+                .args(["-n", program])
+                .args(args.iter().cloned()),
+            // pkexec 会清空环境变量（包括 PATH），需要传绝对路径
+            #[cfg(unix)]
+            Elevation::Pkexec => CommandExecutor::new("pkexec")
+                .arg(absolute_program(program))
+                .args(args.iter().cloned()),
+            #[cfg(unix)]
+            Elevation::SudoAskpass { askpass } => CommandExecutor::new("sudo")
+                .env("SUDO_ASKPASS", askpass.to_string_lossy())
+                .args(["-A", program])
+                .args(args.iter().cloned()),
+            #[cfg(windows)]
+            Elevation::RunAs => {
+                // 提权后的进程在新窗口中运行，通过 -PassThru 取回退出码
+                let argument_list = args.iter().map(|a| powershell_quote(a)).collect::<Vec<_>>().join(",");
+                let mut script = format!(
+                    "$p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru",
+                    powershell_quote(program)
+                );
+                if !args.is_empty() {
+                    script.push_str(&format!(" -ArgumentList {}", argument_list));
+                }
+                script.push_str("; exit $p.ExitCode");
+                CommandExecutor::new("powershell").args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            }
+        }
+    }
+
+    // 用户取消了认证窗口或密码错误
+    fn was_denied(&self, output: &CommandOutput) -> bool {
+        match self {
+            // 126: 用户关闭了认证窗口；127: 未获得授权
+            #[cfg(unix)]
+            Elevation::Pkexec => matches!(output.exit_code, Some(126 | 127)),
+            #[cfg(unix)]
+            Elevation::SudoNoPassword | Elevation::SudoAskpass { .. } => {
+                output.stderr.contains("a password is required")
+                    || output.stderr.contains("no password was provided")
+                    || output.stderr.contains("incorrect password attempt")
+            }
+            #[cfg(windows)]
+            Elevation::RunAs => output.stderr.contains("canceled by the user"),
+            Elevation::AlreadyElevated => false,
+        }
+    }
+}
+
+/// 以管理员权限执行命令并实时发送输出
+///
+/// 无法提权或用户拒绝授权时返回 `ElevationRequired`，其中附带需要用户手动运行的完整命令。
+pub async fn run_elevated(
+    window: &Window,
+    step: &str,
+    program: &str,
+    args: &[String],
+    timeout: Duration,
+    cancel: &CancellationToken,
+) -> InstallerResult<CommandOutput> {
+    let manual = manual_command(program, args);
+    let Some(elevation) = Elevation::detect().await else {
+        return Err(InstallerError::ElevationRequired { command: manual });
+    };
+
+    let output = run_logged(
+        window,
+        step,
+        elevation.command(program, args).timeout(timeout).cancel_token(cancel),
+    )
+    .await?;
+
+    if !output.success {
+        if elevation.was_denied(&output) {
+            return Err(InstallerError::ElevationRequired { command: manual });
+        }
+        return Err(InstallerError::from_output(&manual, &output));
+    }
+    Ok(output)
+}
+
+/// 用户需要在终端中手动运行的命令
+pub fn manual_command(program: &str, args: &[String]) -> String {
+    let command = std::iter::once(program.to_string())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if cfg!(windows) {
+        command
+    } else {
+        format!("sudo {}", command)
+    }
+}
+
+// 辅助函数
+
+async fn probe(command: CommandExecutor) -> bool {
+    command
+        .timeout(PROBE_TIMEOUT)
+        .output()
+        .await
+        .map(|output| output.success)
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn find_askpass() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("SUDO_ASKPASS").map(PathBuf::from) {
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+    ASKPASS_HELPERS.iter().find_map(|helper| which::which(helper).ok())
+}
+
+#[cfg(unix)]
+fn absolute_program(program: &str) -> String {
+    which::which(program)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| program.to_string())
+}
+
+// PowerShell 单引号字符串，内部的单引号写成两个
+#[cfg(windows)]
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::Window;
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::CancellationToken;
+use crate::git_source;
+use crate::installer::{emit_progress, run_checked, SHORT_TIMEOUT};
+use crate::node_env::{home_dir, NodeEnvironment};
+use crate::npm_registry;
+use crate::openclaw_cli::expand_home;
+use crate::upgrade::{detect_install_method, stop_gateway, InstallMethod};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UninstallOptions {
+    /// 删除 ~/.openclaw（配置、凭证、插件）
+    pub remove_config: bool,
+    /// 删除工作区（配置中的 agents.defaults.workspace，默认 ~/clawd）
+    pub remove_workspace: bool,
+    /// 删除已安装的插件（~/.openclaw/extensions）
+    pub remove_plugins: bool,
+    /// 源码安装的目录（默认 ~/.openclaw-src）
+    pub custom_path: Option<String>,
+}
+
+/// 已删除的一项内容
+#[derive(Debug, Serialize, Clone)]
+pub struct RemovedItem {
+    pub kind: String, // "npm-package", "source-checkout", "shim", "plugins", "config", "workspace"
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct UninstallReport {
+    pub removed: Vec<RemovedItem>,
+}
+
+impl UninstallReport {
+    fn push(&mut self, kind: &str, path: impl Into<String>) {
+        self.removed.push(RemovedItem {
+            kind: kind.to_string(),
+            path: path.into(),
+        });
+    }
+}
+
+/// 卸载 OpenClaw：停止网关，删除 npm 全局包或源码目录及 shim，再按选项删除用户数据
+pub async fn uninstall_openclaw(
+    window: &Window,
+    options: UninstallOptions,
+    cancel: &CancellationToken,
+) -> InstallerResult<UninstallReport> {
+    let mut report = UninstallReport::default();
+    let node_env = NodeEnvironment::resolve();
+    let openclaw_dir = home_dir().join(".openclaw");
+    // 配置随后可能被删除，先读出工作区位置
+    let workspace = workspace_dir(&openclaw_dir);
+    if options.remove_workspace {
+        ensure_removable(&workspace, false)?;
+    }
+
+    emit_progress(window, "停止 OpenClaw 网关", "running", 10.0, vec![]);
+    stop_gateway(&node_env, cancel).await;
+
+    emit_progress(window, "删除 OpenClaw 程序", "running", 30.0, vec![]);
+    match detect_install_method(&node_env, options.custom_path.as_deref()) {
+        Some(InstallMethod::Npm) => {
+            run_checked(
+                window,
+                "uninstall",
+                "npm uninstall -g openclaw",
+                node_env
+                    .command("npm")
+                    .args(["uninstall", "-g", npm_registry::PACKAGE_NAME])
+                    .timeout(SHORT_TIMEOUT)
+                    .cancel_token(cancel),
+            )
+            .await?;
+            report.push("npm-package", npm_registry::PACKAGE_NAME);
+        }
+        Some(InstallMethod::Git { path }) => {
+            // 只删除确认是 OpenClaw 源码仓库的目录，shim 记录的路径可能已被改作他用
+            if !git_source::is_openclaw_checkout(&path) {
+                return Err(InstallerError::InvalidOption {
+                    message: format!("{} 不是 OpenClaw 源码仓库，拒绝删除", path.display()),
+                });
+            }
+            remove(&path, "source-checkout", true, &mut report)?;
+        }
+        None => {}
+    }
+    remove_shims(&node_env, &mut report)?;
+
+    if options.remove_plugins {
+        emit_progress(window, "删除已安装的插件", "running", 60.0, vec![]);
+        remove(&openclaw_dir.join("extensions"), "plugins", false, &mut report)?;
+    }
+
+    if options.remove_config {
+        emit_progress(window, "删除配置与凭证", "running", 75.0, vec![]);
+        remove(&openclaw_dir, "config", false, &mut report)?;
+    }
+
+    if options.remove_workspace {
+        emit_progress(window, "删除工作区", "running", 90.0, vec![]);
+        remove(&workspace, "workspace", false, &mut report)?;
+    }
+
+    let logs = report
+        .removed
+        .iter()
+        .map(|item| format!("✓ 已删除 {}", item.path))
+        .collect();
+    emit_progress(window, "卸载完成", "success", 100.0, logs);
+
+    Ok(report)
+}
+
+// 辅助函数
+
+// 删除 PATH 中由安装器写入的 openclaw shim（可能有多个，比如换过 Node.js 版本）
+fn remove_shims(node_env: &NodeEnvironment, report: &mut UninstallReport) -> InstallerResult<()> {
+    let dirs = node_env.bin_dir.iter().cloned().chain([home_dir().join(".npm-global").join("bin")]);
+    for dir in dirs {
+        for name in ["openclaw", "openclaw.cmd"] {
+            let shim = dir.join(name);
+            if git_source::shim_source(&shim).is_some() {
+                remove(&shim, "shim", true, report)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// installed：路径已确认由安装器创建（shim、OpenClaw 源码仓库），允许位于家目录之外
+fn remove(path: &Path, kind: &str, installed: bool, report: &mut UninstallReport) -> InstallerResult<()> {
+    ensure_removable(path, installed)?;
+
+    let result = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => Err(e),
+    };
+
+    result.map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => InstallerError::PermissionDenied {
+            path: path.to_string_lossy().to_string(),
+        },
+        _ => InstallerError::Io {
+            message: format!("删除 {} 失败: {}", path.display(), e),
+        },
+    })?;
+
+    report.push(kind, path.to_string_lossy());
+    Ok(())
+}
+
+// 配置中的工作区可能被设成 /home、/usr 这类目录，删除前先解析出真实路径再检查
+fn ensure_removable(path: &Path, installed: bool) -> InstallerResult<()> {
+    let home = fs::canonicalize(home_dir()).unwrap_or_else(|_| home_dir());
+    if is_removable(&resolve(path), &home, installed) {
+        return Ok(());
+    }
+    Err(InstallerError::InvalidOption {
+        message: format!("拒绝删除 {}", path.display()),
+    })
+}
+
+// 家目录及其上级目录一律不删；家目录之外只删除安装器创建的内容
+fn is_removable(path: &Path, home: &Path, installed: bool) -> bool {
+    if path.parent().is_none() || home.starts_with(path) {
+        return false;
+    }
+    installed || path.starts_with(home)
+}
+
+// 解析上级目录中的符号链接和 ..；最后一段保持原样，删除的是链接本身而不是它指向的内容
+fn resolve(path: &Path) -> PathBuf {
+    match (path.parent().map(fs::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        // 以 .. 结尾等没有文件名的路径
+        _ => fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+fn workspace_dir(openclaw_dir: &Path) -> PathBuf {
+    let configured = fs::read_to_string(openclaw_dir.join("openclaw.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|config| config["agents"]["defaults"]["workspace"].as_str().map(str::to_string));
+    PathBuf::from(expand_home(configured.as_deref().unwrap_or("~/clawd")))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn removable(path: &str, installed: bool) -> bool {
+        is_removable(Path::new(path), Path::new("/home/alice"), installed)
+    }
+
+    #[test]
+    fn refuses_home_and_its_ancestors() {
+        for path in ["/", "/home", "/home/alice"] {
+            assert!(!removable(path, false), "{}", path);
+            assert!(!removable(path, true), "{}", path);
+        }
+    }
+
+    #[test]
+    fn removes_inside_home() {
+        assert!(removable("/home/alice/clawd", false));
+        assert!(removable("/home/alice/.openclaw", false));
+        // 只是名字前缀相同，不在家目录下
+        assert!(!removable("/home/alice2/clawd", false));
+    }
+
+    #[test]
+    fn outside_home_only_installer_created() {
+        assert!(!removable("/usr", false));
+        assert!(!removable("/opt/openclaw-src", false));
+        assert!(removable("/opt/openclaw-src", true));
+        assert!(removable("/usr/local/bin/openclaw", true));
+    }
+
+    #[test]
+    fn resolves_parent_directories() {
+        let home = fs::canonicalize(home_dir()).unwrap_or_else(|_| home_dir());
+        let parent = home.join("..").join(home.file_name().unwrap());
+        assert_eq!(resolve(&parent), home);
+        assert!(ensure_removable(&home.join(".."), false).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::Window;
+
+use crate::error::{InstallerError, InstallerResult};
+use crate::executor::CancellationToken;
+use crate::git_source;
+use crate::journal::{InstallJournal, UndoAction};
+use crate::installer::{emit_progress, run_checked, validate_version_spec, DOWNLOAD_TIMEOUT};
+use crate::node_env::NodeEnvironment;
+use crate::npm_registry;
+use crate::openclaw_cli::OpenClawCli;
+use crate::version::CalVer;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeOptions {
+    /// 目标版本；npm 安装为版本号或 dist-tag，git 安装为分支、tag 或提交。为空时升级到最新
+    pub version: Option<String>,
+    /// 源码安装的目录（默认 ~/.openclaw-src）
+    pub custom_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeReport {
+    pub method: String, // "npm" or "git"
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+}
+
+/// OpenClaw 当前的安装方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallMethod {
+    /// `npm install -g openclaw`
+    Npm,
+    /// 源码检出目录
+    Git { path: PathBuf },
+}
+
+impl InstallMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InstallMethod::Npm => "npm",
+            InstallMethod::Git { .. } => "git",
+        }
+    }
+}
+
+/// 判断 OpenClaw 是通过 npm 全局安装，还是来自源码检出目录
+///
+/// 安装器写入的 shim 直接记录了源码目录；否则顺着 openclaw 可执行文件的符号链接找到真实路径，
+/// 若位于 OpenClaw 源码仓库中（且不在 node_modules 下）则为源码安装；
+/// 找不到可执行文件时，再看默认（或指定）的源码目录是否为 OpenClaw 源码仓库。
+pub fn detect_install_method(
+    node_env: &NodeEnvironment,
+    custom_path: Option<&str>,
+) -> Option<InstallMethod> {
+    if let Some(program) = node_env.which("openclaw") {
+        if let Some(repo) = git_source::shim_source(&program) {
+            return Some(InstallMethod::Git { path: repo });
+        }
+        let real = std::fs::canonicalize(&program).unwrap_or(program);
+        // npm 全局包位于 node_modules 下，而 ~/.nvm、/opt/homebrew 这些上级目录本身也是 git 仓库
+        let in_node_modules = real.components().any(|c| c.as_os_str() == "node_modules");
+        if !in_node_modules {
+            if let Some(repo) = real.ancestors().find(|dir| git_source::is_openclaw_checkout(dir)) {
+                return Some(InstallMethod::Git { path: repo.to_path_buf() });
+            }
+        }
+        return Some(InstallMethod::Npm);
+    }
+
+    let checkout = git_source::source_dir(custom_path);
+    if git_source::is_openclaw_checkout(&checkout) {
+        return Some(InstallMethod::Git { path: checkout });
+    }
+    None
+}
+
+/// 按原安装渠道升级 OpenClaw，升级前后停止并重新启动网关
+pub async fn upgrade_openclaw(
+    window: &Window,
+    options: UpgradeOptions,
+    cancel: &CancellationToken,
+) -> InstallerResult<UpgradeReport> {
+    emit_progress(window, "检测 OpenClaw 安装方式", "running", 5.0, vec![]);
+
+    let node_env = NodeEnvironment::resolve();
+    let method = detect_install_method(&node_env, options.custom_path.as_deref()).ok_or_else(|| {
+        InstallerError::CommandNotFound {
+            program: "openclaw".to_string(),
+        }
+    })?;
+    let version = options.version.as_deref().map(validate_version_spec).transpose()?;
+    let old_version = current_version(&node_env).await;
+
+    emit_progress(window, "停止 OpenClaw 网关", "running", 15.0, vec![
+        format!("安装方式: {}", method.name()),
+        format!("当前版本: {}", old_version.as_deref().unwrap_or("unknown")),
+    ]);
+    let gateway_was_running = stop_gateway(&node_env, cancel).await;
+
+    let mut journal = InstallJournal::new(window);
+    let upgrade_result = match &method {
+        InstallMethod::Npm => upgrade_npm(window, &node_env, version, &mut journal, cancel).await,
+        InstallMethod::Git { path } => {
+            upgrade_git(window, &node_env, path, version, &mut journal, cancel).await
+        }
+    };
+    // 升级失败时回到升级前的版本
+    match &upgrade_result {
+        Ok(()) => journal.commit(),
+        Err(_) => {
+            journal.rollback(window).await;
+        }
+    }
+
+    // 无论升级成功、失败还是被取消（已回滚到旧版本），都恢复网关；重启不受取消令牌影响
+    if gateway_was_running {
+        emit_progress(window, "重新启动 OpenClaw 网关", "running", 90.0, vec![]);
+        if let Ok(cli) = OpenClawCli::resolve() {
+            let _ = cli.run(["gateway", "start"]).await;
+        }
+    }
+    upgrade_result?;
+
+    let new_version = current_version(&NodeEnvironment::resolve()).await;
+    emit_progress(window, "升级完成", "success", 100.0, vec![
+        format!(
+            "✓ OpenClaw {} → {}",
+            old_version.as_deref().unwrap_or("unknown"),
+            new_version.as_deref().unwrap_or("unknown"),
+        ),
+    ]);
+
+    Ok(UpgradeReport {
+        method: method.name().to_string(),
+        old_version,
+        new_version,
+    })
+}
+
+async fn upgrade_npm(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    version: Option<&str>,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    // 版本未知时无法恢复，也不能卸载用户原有的安装
+    if let Some(previous) = installed_version(node_env).await {
+        journal.record(npm_undo(Some(&previous)));
+    }
+
+    let target = match version {
+        Some(version) => version.to_string(),
+        None => npm_registry::latest_version(node_env)
+            .await
+            .unwrap_or_else(|_| "latest".to_string()),
+    };
+    let spec = format!("{}@{}", npm_registry::PACKAGE_NAME, target);
+
+    emit_progress(window, &format!("npm install -g {}", spec), "running", 30.0, vec![]);
+    run_checked(
+        window,
+        "openclaw",
+        "npm install -g openclaw",
+        node_env
+            .command("npm")
+            .args(["install", "-g", spec.as_str()])
+            .timeout(DOWNLOAD_TIMEOUT)
+            .cancel_token(cancel),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn upgrade_git(
+    window: &Window,
+    node_env: &NodeEnvironment,
+    repo: &Path,
+    git_ref: Option<&str>,
+    journal: &mut InstallJournal,
+    cancel: &CancellationToken,
+) -> InstallerResult<()> {
+    let node_env = git_source::ensure_pnpm(window, node_env, journal, cancel).await?;
+    git_source::sync_checkout(window, repo, git_ref, journal, cancel).await?;
+    git_source::build(window, &node_env, repo, cancel).await?;
+    git_source::link_cli(&node_env, repo, journal)?;
+    Ok(())
+}
+
+// 停止网关，返回停止前是否在运行（stop 成功即视为在运行）
+pub(crate) async fn stop_gateway(node_env: &NodeEnvironment, cancel: &CancellationToken) -> bool {
+    let Ok(cli) = OpenClawCli::from_env(node_env) else {
+        return false;
+    };
+    cli.command(["gateway", "stop"])
+        .cancel_token(cancel)
+        .output()
+        .await
+        .map(|output| output.success)
+        .unwrap_or(false)
+}
+
+/// 当前安装的 openclaw 版本（未安装或无法运行时为 None）
+pub(crate) async fn current_version(node_env: &NodeEnvironment) -> Option<String> {
+    installed_version(node_env).await.map(|v| v.to_string())
+}
+
+pub(crate) async fn installed_version(node_env: &NodeEnvironment) -> Option<CalVer> {
+    let cli = OpenClawCli::from_env(node_env).ok()?;
+    let output = cli.run(["--version"]).await.ok()?;
+    CalVer::parse(&output.stdout)
+}
+
+/// npm 安装或升级的撤销操作：重新安装原来的版本，原来没有安装则卸载
+///
+/// `openclaw --version` 带有提交哈希，npm 只接受发布的版本号。
+pub(crate) fn npm_undo(previous: Option<&CalVer>) -> UndoAction {
+    let args = match previous {
+        Some(previous) => vec![
+            "install".to_string(),
+            "-g".to_string(),
+            format!("{}@{}", npm_registry::PACKAGE_NAME, previous.release()),
+        ],
+        None => vec![
+            "uninstall".to_string(),
+            "-g".to_string(),
+            npm_registry::PACKAGE_NAME.to_string(),
+        ],
+    };
+    UndoAction::Command {
+        program: "npm".to_string(),
+        args,
+        cwd: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn undo_args(action: UndoAction) -> Vec<String> {
+        match action {
+            UndoAction::Command { program, args, cwd } => {
+                assert_eq!(program, "npm");
+                assert_eq!(cwd, None);
+                args
+            }
+            other => panic!("unexpected undo action {:?}", other),
+        }
+    }
+
+    #[test]
+    fn npm_undo_reinstalls_release_without_commit() {
+        let previous = CalVer::parse("🦞 OpenClaw 2026.2.14 (c1feda1)").unwrap();
+        assert_eq!(
+            undo_args(npm_undo(Some(&previous))),
+            ["install", "-g", "openclaw@2026.2.14"]
+        );
+    }
+
+    #[test]
+    fn npm_undo_uninstalls_when_nothing_was_installed() {
+        assert_eq!(undo_args(npm_undo(None)), ["uninstall", "-g", "openclaw"]);
+    }
+}
@@ -0,0 +1,275 @@
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::OnceLock;
+
+// 检测时每个依赖都要解析版本，正则只编译一次
+static SEMVER_RE: OnceLock<Regex> = OnceLock::new();
+static CALVER_RE: OnceLock<Regex> = OnceLock::new();
+
+/// 语义化版本（Node.js、npm、git、nvm 等），缺省的部分视为 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl SemVer {
+    /// 从任意文本中提取第一个版本号，如 "v22.1.0"、"git version 2.39.3 (Apple Git-145)"、"22"
+    pub fn parse(text: &str) -> Option<Self> {
+        let re = SEMVER_RE.get_or_init(|| Regex::new(r"(\d+)(?:\.(\d+))?(?:\.(\d+))?").unwrap());
+        let caps = re.captures(text)?;
+        let part = |i: usize| caps.get(i).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+        Some(Self {
+            major: caps.get(1)?.as_str().parse().ok()?,
+            minor: part(2),
+            patch: part(3),
+        })
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// OpenClaw 的日历版本 `YYYY.M.D`，源码构建时附带提交哈希
+///
+/// 比较时只看日期，提交哈希不参与排序。
+#[derive(Debug, Clone)]
+pub struct CalVer {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub commit: Option<String>,
+}
+
+impl CalVer {
+    /// 解析 "2026.2.14"、"🦞 OpenClaw 2026.2.14 (c1feda1)" 等格式
+    pub fn parse(text: &str) -> Option<Self> {
+        let re = CALVER_RE.get_or_init(|| {
+            Regex::new(r"\b(\d{4})\.(\d{1,2})\.(\d{1,2})\b(?:\S*\s*\(([0-9a-f]{7,40})\))?").unwrap()
+        });
+        let caps = re.captures(text)?;
+        Some(Self {
+            year: caps.get(1)?.as_str().parse().ok()?,
+            month: caps.get(2)?.as_str().parse().ok()?,
+            day: caps.get(3)?.as_str().parse().ok()?,
+            commit: caps.get(4).map(|m| m.as_str().to_string()),
+        })
+    }
+
+    /// 不含提交哈希的版本号，即 npm 上发布的版本，如 "2026.2.14"
+    pub fn release(&self) -> String {
+        format!("{}.{}.{}", self.year, self.month, self.day)
+    }
+
+    fn date(&self) -> (u32, u32, u32) {
+        (self.year, self.month, self.day)
+    }
+}
+
+impl PartialEq for CalVer {
+    fn eq(&self, other: &Self) -> bool {
+        self.date() == other.date()
+    }
+}
+
+impl Eq for CalVer {}
+
+impl PartialOrd for CalVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CalVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.date().cmp(&other.date())
+    }
+}
+
+impl fmt::Display for CalVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.release())?;
+        if let Some(commit) = &self.commit {
+            write!(f, " ({})", commit)?;
+        }
+        Ok(())
+    }
+}
+
+/// 日历版本或语义化版本；两种版本之间不可比较
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Version {
+    Calendar(CalVer),
+    Semantic(SemVer),
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Self> {
+        CalVer::parse(text)
+            .map(Version::Calendar)
+            .or_else(|| SemVer::parse(text).map(Version::Semantic))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Version::Calendar(a), Version::Calendar(b)) => Some(a.cmp(b)),
+            (Version::Semantic(a), Version::Semantic(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Version::Calendar(v) => v.fmt(f),
+            Version::Semantic(v) => v.fmt(f),
+        }
+    }
+}
+
+/// `DependencyStatus.required_version` 中的版本要求
+///
+/// 支持 "22+"、">=10"、"2026.2.14"、"latest"、"any"。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionRequirement {
+    Any,
+    /// 需要最新版本，但最新版本号未知
+    Latest,
+    AtLeast(Version),
+}
+
+impl VersionRequirement {
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        match text.to_lowercase().as_str() {
+            "" | "any" | "*" => return VersionRequirement::Any,
+            "latest" => return VersionRequirement::Latest,
+            _ => {}
+        }
+        let bare = text.trim_start_matches(">=").trim_end_matches('+');
+        Version::parse(bare)
+            .map(VersionRequirement::AtLeast)
+            .unwrap_or(VersionRequirement::Any)
+    }
+
+    /// 当前版本是否满足要求；无法判断时返回 None
+    pub fn is_satisfied_by(&self, current: &str) -> Option<bool> {
+        match self {
+            VersionRequirement::Any => Some(true),
+            VersionRequirement::Latest => None,
+            VersionRequirement::AtLeast(minimum) => {
+                let current = Version::parse(current)?;
+                current.partial_cmp(minimum).map(|ord| ord != Ordering::Less)
+            }
+        }
+    }
+}
+
+/// 已安装的版本是否低于要求（无法判断时视为不需要更新）
+pub fn needs_update(current: Option<&str>, required: &str) -> bool {
+    current
+        .and_then(|current| VersionRequirement::parse(required).is_satisfied_by(current))
+        .map(|satisfied| !satisfied)
+        .unwrap_or(false)
+}
+
+/// 读取可配置的最低版本：环境变量 `OPENCLAW_INSTALLER_MIN_<NAME>` 优先，否则使用默认值
+///
+/// 例如 `OPENCLAW_INSTALLER_MIN_OPENCLAW=2026.2.14`、`OPENCLAW_INSTALLER_MIN_NODEJS=22.12`。
+pub fn configured_minimum(name: &str, default: &str) -> String {
+    let key = format!(
+        "OPENCLAW_INSTALLER_MIN_{}",
+        name.to_uppercase().replace('-', "_")
+    );
+    std::env::var(key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn semver(major: u64, minor: u64, patch: u64) -> SemVer {
+        SemVer { major, minor, patch }
+    }
+
+    #[test]
+    fn parses_openclaw_version_output() {
+        let version = CalVer::parse("🦞 OpenClaw 2026.2.14 (c1feda1)").unwrap();
+        assert_eq!((version.year, version.month, version.day), (2026, 2, 14));
+        assert_eq!(version.commit.as_deref(), Some("c1feda1"));
+        assert_eq!(version.to_string(), "2026.2.14 (c1feda1)");
+        assert_eq!(version.release(), "2026.2.14");
+
+        let plain = CalVer::parse("2026.2.14").unwrap();
+        assert_eq!(plain.commit, None);
+        // 提交哈希不参与比较
+        assert_eq!(version, plain);
+    }
+
+    #[test]
+    fn parses_semver_from_tool_output() {
+        assert_eq!(SemVer::parse("git version 2.39.3 (Apple Git-145)"), Some(semver(2, 39, 3)));
+        assert_eq!(SemVer::parse("v22.1.0"), Some(semver(22, 1, 0)));
+        assert_eq!(SemVer::parse("22"), Some(semver(22, 0, 0)));
+        assert_eq!(SemVer::parse("not installed"), None);
+    }
+
+    #[test]
+    fn version_prefers_calendar_format() {
+        assert!(matches!(Version::parse("OpenClaw 2026.2.14"), Some(Version::Calendar(_))));
+        assert!(matches!(Version::parse("v22.1.0"), Some(Version::Semantic(_))));
+    }
+
+    #[test]
+    fn parses_requirements() {
+        assert_eq!(
+            VersionRequirement::parse("22+"),
+            VersionRequirement::AtLeast(Version::Semantic(semver(22, 0, 0)))
+        );
+        assert_eq!(
+            VersionRequirement::parse(">=10"),
+            VersionRequirement::AtLeast(Version::Semantic(semver(10, 0, 0)))
+        );
+        assert_eq!(VersionRequirement::parse("latest"), VersionRequirement::Latest);
+        assert_eq!(VersionRequirement::parse("any"), VersionRequirement::Any);
+        assert_eq!(VersionRequirement::parse(""), VersionRequirement::Any);
+    }
+
+    #[test]
+    fn checks_requirements() {
+        let node = VersionRequirement::parse("22+");
+        assert_eq!(node.is_satisfied_by("v22.1.0"), Some(true));
+        assert_eq!(node.is_satisfied_by("v20.11.1"), Some(false));
+        assert_eq!(node.is_satisfied_by("unknown"), None);
+
+        assert_eq!(VersionRequirement::parse(">=10").is_satisfied_by("10.0.0"), Some(true));
+        assert_eq!(VersionRequirement::parse("any").is_satisfied_by("1.0.0"), Some(true));
+        assert_eq!(VersionRequirement::parse("latest").is_satisfied_by("2026.2.14"), None);
+
+        let openclaw = VersionRequirement::parse("2026.2.14");
+        assert_eq!(openclaw.is_satisfied_by("🦞 OpenClaw 2026.3.1 (abcdef0)"), Some(true));
+        assert_eq!(openclaw.is_satisfied_by("2026.1.30"), Some(false));
+    }
+
+    #[test]
+    fn calendar_and_semantic_versions_are_incomparable() {
+        let calendar = Version::parse("2026.2.14").unwrap();
+        let semantic = Version::parse("22.1.0").unwrap();
+        assert_eq!(calendar.partial_cmp(&semantic), None);
+        assert_eq!(VersionRequirement::parse("22+").is_satisfied_by("2026.2.14"), None);
+        // 无法判断时不提示更新
+        assert!(!needs_update(Some("2026.2.14"), "22+"));
+        assert!(needs_update(Some("v20.0.0"), "22+"));
+    }
+}